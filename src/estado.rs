@@ -0,0 +1,249 @@
+use std::{collections::HashMap, env, error::Error, sync::{atomic::{AtomicBool, Ordering}, Arc, LazyLock, OnceLock}};
+
+use redb::{Database, ReadableTable, TableDefinition};
+use serde::Serialize;
+use tokio::{sync::{Mutex, MutexGuard, OwnedMutexGuard}, task};
+
+use crate::{falha::Falha, nfe::ciclo_vida::{EstadoDocumento, Ocorrencia}};
+
+/// Estado projetado de cada NF-e, serializado em JSON e indexado pela chave de acesso.
+const DOCUMENTOS: TableDefinition<&str, &[u8]> = TableDefinition::new("documentos");
+/// Mudanças de situação ainda não publicadas (outbox), em ordem de gravação.
+const OUTBOX_STATUS: TableDefinition<u64, &[u8]> = TableDefinition::new("status_pendente");
+
+/// Id no outbox e JSON da [`MudancaStatus`].
+pub type StatusPendente = (u64, Vec<u8>);
+
+static STORE: OnceLock<EstadoStore> = OnceLock::new();
+/// Uma trava por chave com processamento em andamento, removida quando ninguém mais a usa.
+static CHAVES: LazyLock<std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>> = LazyLock::new(Default::default);
+/// Serializa o envio do outbox de situações entre os canais de consumo, preservando a ordem.
+static ENVIO_STATUS: Mutex<()> = Mutex::const_new(());
+/// Há mudanças de situação no outbox que ainda não foram publicadas (inclusive de execuções anteriores).
+static STATUS_PENDENTE: AtomicBool = AtomicBool::new(true);
+
+pub struct EstadoVariables {
+    pub db_path: Option<String>,
+}
+
+/// Banco local (redb) com a situação de cada NF-e, alimentado pelos protocolos e eventos consumidos.
+pub struct EstadoStore {
+    db: Database,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Mudanca {
+    pub chave: String,
+    pub anterior: Option<EstadoDocumento>,
+    pub atual: EstadoDocumento,
+}
+
+/// Mensagem publicada quando a situação projetada de uma NF-e muda.
+#[derive(Debug, Serialize)]
+pub struct MudancaStatus {
+    pub company_id: i64,
+    pub org_id: i64,
+    #[serde(flatten)]
+    pub mudanca: Mudanca,
+}
+
+impl EstadoStore {
+    pub fn abrir(caminho: &str) -> Result<EstadoStore, Box<dyn Error>> {
+        let db: Database = Database::create(caminho)?;
+        let txn = db.begin_write()?;
+        txn.open_table(DOCUMENTOS)?;
+        txn.open_table(OUTBOX_STATUS)?;
+        txn.commit()?;
+        Ok(EstadoStore { db })
+    }
+
+    /// Confere as ocorrências sem gravar. Ocorrências recusadas (CC-e fora de ordem) retornam a falha
+    /// antes que o resultado seja publicado.
+    pub fn verificar(&self, ocorrencias: &[Ocorrencia]) -> Result<(), Falha> {
+        self.verificar_transacao(ocorrencias).map_err(classificar_erro)
+    }
+
+    fn verificar_transacao(&self, ocorrencias: &[Ocorrencia]) -> Result<(), Box<dyn Error>> {
+        if ocorrencias.is_empty() {
+            return Ok(());
+        }
+        let txn = self.db.begin_read()?;
+        projetar(&txn.open_table(DOCUMENTOS)?, ocorrencias)?;
+        Ok(())
+    }
+
+    /// Aplica as ocorrências em uma única transação, gravando as mudanças de situação na fila de
+    /// publicação (outbox) junto com o novo estado. Retorna quantas mudanças foram registradas.
+    /// Se alguma ocorrência for recusada, nada é gravado. Erros do banco são transitórios.
+    pub fn aplicar(&self, ocorrencias: &[Ocorrencia], company_id: i64, org_id: i64) -> Result<usize, Falha> {
+        self.aplicar_transacao(ocorrencias, company_id, org_id).map_err(classificar_erro)
+    }
+
+    fn aplicar_transacao(&self, ocorrencias: &[Ocorrencia], company_id: i64, org_id: i64) -> Result<usize, Box<dyn Error>> {
+        if ocorrencias.is_empty() {
+            return Ok(0);
+        }
+
+        let txn = self.db.begin_write()?;
+        let total: usize;
+        {
+            let mut tabela = txn.open_table(DOCUMENTOS)?;
+            let mut pendentes = txn.open_table(OUTBOX_STATUS)?;
+            let mudancas: Vec<Mudanca> = projetar(&tabela, ocorrencias)?;
+            total = mudancas.len();
+
+            let proximo: u64 = match pendentes.last()? {
+                Some((id, _)) => id.value() + 1,
+                None => 0,
+            };
+            for (id, mudanca) in (proximo..).zip(mudancas) {
+                tabela.insert(mudanca.chave.as_str(), serde_json::to_vec(&mudanca.atual)?.as_slice())?;
+                let status: MudancaStatus = MudancaStatus { company_id, org_id, mudanca };
+                pendentes.insert(id, serde_json::to_vec(&status)?.as_slice())?;
+            }
+        }
+        txn.commit()?;
+        Ok(total)
+    }
+
+    /// Mudanças de situação ainda não publicadas, com o id usado para removê-las após a publicação.
+    pub fn status_pendentes(&self) -> Result<Vec<StatusPendente>, Falha> {
+        let ler = || -> Result<Vec<StatusPendente>, Box<dyn Error>> {
+            let txn = self.db.begin_read()?;
+            let tabela = txn.open_table(OUTBOX_STATUS)?;
+            let mut pendentes: Vec<StatusPendente> = Vec::new();
+            for item in tabela.iter()? {
+                let (id, valor) = item?;
+                pendentes.push((id.value(), valor.value().to_vec()));
+            }
+            Ok(pendentes)
+        };
+        ler().map_err(classificar_erro)
+    }
+
+    pub fn remover_status(&self, id: u64) -> Result<(), Falha> {
+        let remover = || -> Result<(), Box<dyn Error>> {
+            let txn = self.db.begin_write()?;
+            txn.open_table(OUTBOX_STATUS)?.remove(id)?;
+            txn.commit()?;
+            Ok(())
+        };
+        remover().map_err(classificar_erro)
+    }
+}
+
+fn classificar_erro(e: Box<dyn Error>) -> Falha {
+    match e.downcast::<Falha>() {
+        Ok(falha) => *falha,
+        Err(e) => Falha::transitoria("state_unavailable", e),
+    }
+}
+
+/// Situação resultante de cada NF-e afetada, apenas para as que mudaram.
+fn projetar(tabela: &impl ReadableTable<&'static str, &'static [u8]>, ocorrencias: &[Ocorrencia]) -> Result<Vec<Mudanca>, Box<dyn Error>> {
+    let mut estados: HashMap<&str, (Option<EstadoDocumento>, EstadoDocumento)> = HashMap::new();
+
+    for ocorrencia in ocorrencias {
+        let chave: &str = ocorrencia.chave.as_str();
+        if !estados.contains_key(chave) {
+            let anterior: Option<EstadoDocumento> = match tabela.get(chave)? {
+                Some(valor) => Some(serde_json::from_slice(valor.value())?),
+                None => None,
+            };
+            let atual: EstadoDocumento = anterior.clone().unwrap_or_default();
+            estados.insert(chave, (anterior, atual));
+        }
+        if let Some((_, atual)) = estados.get_mut(chave) {
+            atual.aplicar(ocorrencia)?;
+        }
+    }
+
+    Ok(estados
+        .into_iter()
+        .filter(|(_, (anterior, atual))| anterior.as_ref() != Some(atual))
+        .map(|(chave, (anterior, atual))| Mudanca { chave: chave.to_string(), anterior, atual })
+        .collect())
+}
+
+/// Trava das chaves de uma mensagem, mantida da conferência à gravação do estado.
+pub struct BloqueioChaves {
+    travas: Vec<(String, OwnedMutexGuard<()>)>,
+}
+
+impl Drop for BloqueioChaves {
+    fn drop(&mut self) {
+        let mut chaves = CHAVES.lock().unwrap_or_else(|e| e.into_inner());
+        for (chave, trava) in self.travas.drain(..) {
+            drop(trava);
+            if chaves.get(&chave).is_some_and(|t| Arc::strong_count(t) == 1) {
+                chaves.remove(&chave);
+            }
+        }
+    }
+}
+
+/// Serializa o processamento por chave de acesso: com vários canais, duas mensagens da mesma NF-e
+/// não conferem o estado antes que a outra o grave. As chaves são travadas em ordem, evitando
+/// impasse entre mensagens com várias chaves. Sem o banco de estado, não trava nada.
+pub async fn bloquear(ocorrencias: &[Ocorrencia]) -> Option<BloqueioChaves> {
+    get_store()?;
+    let mut chaves: Vec<&str> = ocorrencias.iter().map(|o| o.chave.as_str()).collect();
+    chaves.sort_unstable();
+    chaves.dedup();
+
+    let mut bloqueio: BloqueioChaves = BloqueioChaves { travas: Vec::with_capacity(chaves.len()) };
+    for chave in chaves {
+        let trava: Arc<Mutex<()>> = {
+            let mut travas = CHAVES.lock().unwrap_or_else(|e| e.into_inner());
+            travas.entry(chave.to_string()).or_default().clone()
+        };
+        bloqueio.travas.push((chave.to_string(), trava.lock_owned().await));
+    }
+    Some(bloqueio)
+}
+
+/// Trava o envio do outbox; quem a detém publica as mudanças pendentes em ordem.
+pub async fn travar_envio_status() -> MutexGuard<'static, ()> {
+    ENVIO_STATUS.lock().await
+}
+
+pub fn status_pendente() -> bool {
+    STATUS_PENDENTE.load(Ordering::Relaxed)
+}
+
+pub fn marcar_status_pendente(pendente: bool) {
+    STATUS_PENDENTE.store(pendente, Ordering::Relaxed);
+}
+
+/// Executa a operação no banco no pool de threads bloqueantes: as transações de escrita do redb
+/// são serializadas e fazem fsync no commit.
+pub async fn executar<T: Send + 'static>(operacao: impl FnOnce(&'static EstadoStore) -> Result<T, Falha> + Send + 'static) -> Result<Option<T>, Falha> {
+    let Some(store) = get_store() else {
+        return Ok(None);
+    };
+    match task::spawn_blocking(move || operacao(store)).await {
+        Ok(resultado) => resultado.map(Some),
+        Err(e) => Err(Falha::transitoria("state_unavailable", e)),
+    }
+}
+
+/// Abre o banco quando `ESTADO_DB_PATH` estiver definido. Sem ele, a projeção fica desabilitada.
+pub fn init_store(variables: &EstadoVariables) -> Option<&'static EstadoStore> {
+    let caminho: &str = variables.db_path.as_deref()?;
+    let store: EstadoStore = match EstadoStore::abrir(caminho) {
+        Ok(store) => store,
+        Err(e) => panic!("Could not open state database {}: {}", caminho, e),
+    };
+    log::info!("Lifecycle state database opened at {}", caminho);
+    Some(STORE.get_or_init(|| store))
+}
+
+pub fn get_store() -> Option<&'static EstadoStore> {
+    STORE.get()
+}
+
+pub fn initialize_variables() -> EstadoVariables {
+    EstadoVariables {
+        db_path: env::var("ESTADO_DB_PATH").ok().filter(|p| !p.trim().is_empty()),
+    }
+}
@@ -0,0 +1,76 @@
+use std::{error::Error, fmt::{self, Display}};
+
+/// Classe da falha ao processar uma mensagem. Define o destino da mensagem no consumidor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ClasseFalha {
+    /// Indisponibilidade momentânea (rede, MinIO, RabbitMQ): nova tentativa pelas filas de espera.
+    Transitoria,
+    /// Mensagem ou documento inválido, que nunca será processado: Dead Letter Queue.
+    Permanente,
+    /// Documento válido que o serviço não sabe tratar sozinho: fila de revisão manual.
+    RevisaoManual,
+}
+
+impl ClasseFalha {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClasseFalha::Transitoria => "transient",
+            ClasseFalha::Permanente => "permanent",
+            ClasseFalha::RevisaoManual => "needs_human",
+        }
+    }
+}
+
+impl Display for ClasseFalha {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Falha classificada, com um motivo estável (usado em logs e métricas) e o erro original.
+#[derive(Debug)]
+pub struct Falha {
+    pub classe: ClasseFalha,
+    pub motivo: &'static str,
+    pub erro: String,
+    /// Arquivo da mensagem, quando ela já foi decodificada.
+    pub arquivo: Option<String>,
+    /// Caminho do elemento XML que falhou, quando conhecido.
+    pub caminho: Option<String>,
+}
+
+impl Falha {
+    pub fn new(classe: ClasseFalha, motivo: &'static str, erro: impl Display) -> Self {
+        Falha { classe, motivo, erro: erro.to_string(), arquivo: None, caminho: None }
+    }
+
+    pub fn transitoria(motivo: &'static str, erro: impl Display) -> Self {
+        Falha::new(ClasseFalha::Transitoria, motivo, erro)
+    }
+
+    pub fn permanente(motivo: &'static str, erro: impl Display) -> Self {
+        Falha::new(ClasseFalha::Permanente, motivo, erro)
+    }
+
+    pub fn revisao_manual(motivo: &'static str, erro: impl Display) -> Self {
+        Falha::new(ClasseFalha::RevisaoManual, motivo, erro)
+    }
+
+    pub fn com_arquivo(mut self, arquivo: &str) -> Self {
+        self.arquivo = Some(arquivo.to_string());
+        self
+    }
+
+    pub fn com_caminho(mut self, caminho: &str) -> Self {
+        self.caminho = Some(caminho.to_string());
+        self
+    }
+}
+
+impl Error for Falha {}
+
+impl Display for Falha {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}/{}] {}", self.classe, self.motivo, self.erro)
+    }
+}
@@ -1,23 +1,42 @@
 mod logger;
 mod rabbitmq;
 mod minio_client;
+mod falha;
+mod estado;
+mod metricas;
 
 mod nfe_parser;
+mod parse_pool;
 mod nfes;
 mod nfe;
 
+use std::process::ExitCode;
+
 use dotenv::dotenv;
 
-use crate::{minio_client::MinioVariables, rabbitmq::{common::{initialize_variables, RabbitVariables}, consumer::RabbitMqConsumer}};
+use crate::{minio_client::MinioVariables, nfe::config::ParserVariables, rabbitmq::{common::{initialize_variables, RabbitVariables}, consumer::RabbitMqConsumer}};
 
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     dotenv().ok();
     logger::register_logger();
 
     let minio_variables: MinioVariables = minio_client::initialize_variables();
     minio_client::init_client(&minio_variables);
+
+    let parser_variables: ParserVariables = nfe::config::initialize_variables();
+    nfe::config::init_config(parser_variables);
+    if let Err(e) = nfe::tabelas::inicializar() {
+        log::error!("{}", e);
+        return ExitCode::FAILURE;
+    }
+
+    let estado_variables: estado::EstadoVariables = estado::initialize_variables();
+    estado::init_store(&estado_variables);
+
+    let metricas_variables: metricas::MetricasVariables = metricas::initialize_variables();
+    metricas::init_server(&metricas_variables).await;
     
     
     let consumer_variables: RabbitVariables = initialize_variables();
@@ -28,6 +47,5 @@ async fn main() {
         minio_variables.bucket_name).await;
 
 
-    consumer.start().await;
-    
+    consumer.start().await
 }
\ No newline at end of file
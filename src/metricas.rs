@@ -0,0 +1,162 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::falha::{ClasseFalha, Falha};
+
+pub struct MetricasVariables {
+    pub porta: Option<u16>,
+}
+
+/// Contadores do processo, publicados periodicamente no log pelo consumidor e em `/metrics`.
+static PROCESSADAS: AtomicU64 = AtomicU64::new(0);
+static FALHAS: Mutex<BTreeMap<(ClasseFalha, &'static str), u64>> = Mutex::new(BTreeMap::new());
+
+/// Documentos aguardando uma vaga no pool de parse, em parse, e o maior tamanho da fila desde o último resumo.
+static FILA_PARSE: AtomicI64 = AtomicI64::new(0);
+static PARSE_ATIVOS: AtomicI64 = AtomicI64::new(0);
+static FILA_PARSE_PICO: AtomicI64 = AtomicI64::new(0);
+
+/// Medidor de ocupação: incrementa ao ser criado e decrementa ao sair de escopo.
+pub struct Medidor(&'static AtomicI64);
+
+impl Drop for Medidor {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub fn entrar_fila_parse() -> Medidor {
+    let fila: i64 = FILA_PARSE.fetch_add(1, Ordering::Relaxed) + 1;
+    FILA_PARSE_PICO.fetch_max(fila, Ordering::Relaxed);
+    Medidor(&FILA_PARSE)
+}
+
+pub fn iniciar_parse() -> Medidor {
+    PARSE_ATIVOS.fetch_add(1, Ordering::Relaxed);
+    Medidor(&PARSE_ATIVOS)
+}
+
+pub fn registrar_sucesso() {
+    PROCESSADAS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn registrar_falha(falha: &Falha) {
+    if let Ok(mut falhas) = FALHAS.lock() {
+        *falhas.entry((falha.classe, falha.motivo)).or_insert(0) += 1;
+    }
+}
+
+/// Resumo no formato `processed=N parse_queue=N parse_queue_peak=N parse_active=N failures{class=..,reason=..}=N ...`.
+/// O pico da fila de parse é reiniciado a cada resumo.
+pub fn resumo() -> String {
+    let fila: i64 = FILA_PARSE.load(Ordering::Relaxed);
+    let mut resumo: String = format!(
+        "processed={} parse_queue={} parse_queue_peak={} parse_active={}",
+        PROCESSADAS.load(Ordering::Relaxed),
+        fila,
+        FILA_PARSE_PICO.swap(fila, Ordering::Relaxed),
+        PARSE_ATIVOS.load(Ordering::Relaxed)
+    );
+    if let Ok(falhas) = FALHAS.lock() {
+        for ((classe, motivo), total) in falhas.iter() {
+            resumo.push_str(&format!(" failures{{class={},reason={}}}={}", classe, motivo, total));
+        }
+    }
+    resumo
+}
+
+/// Contadores no formato de texto do Prometheus.
+pub fn prometheus() -> String {
+    let mut texto: String = String::new();
+    texto.push_str("# HELP parser_processed_total Messages processed and acknowledged.\n");
+    texto.push_str("# TYPE parser_processed_total counter\n");
+    writeln!(texto, "parser_processed_total {}", PROCESSADAS.load(Ordering::Relaxed)).ok();
+
+    texto.push_str("# HELP parser_parse_queue Documents waiting for a parse pool slot.\n");
+    texto.push_str("# TYPE parser_parse_queue gauge\n");
+    writeln!(texto, "parser_parse_queue {}", FILA_PARSE.load(Ordering::Relaxed)).ok();
+    texto.push_str("# HELP parser_parse_active Documents being parsed.\n");
+    texto.push_str("# TYPE parser_parse_active gauge\n");
+    writeln!(texto, "parser_parse_active {}", PARSE_ATIVOS.load(Ordering::Relaxed)).ok();
+
+    texto.push_str("# HELP parser_failures_total Failed messages by class and reason.\n");
+    texto.push_str("# TYPE parser_failures_total counter\n");
+    if let Ok(falhas) = FALHAS.lock() {
+        for ((classe, motivo), total) in falhas.iter() {
+            writeln!(texto, "parser_failures_total{{class=\"{}\",reason=\"{}\"}} {}", classe, motivo, total).ok();
+        }
+    }
+    texto
+}
+
+/// Abre o endpoint `GET /metrics` na porta configurada. Sem `METRICS_PORT`, as métricas ficam só no log.
+pub async fn init_server(variables: &MetricasVariables) {
+    let Some(porta) = variables.porta else {
+        return;
+    };
+    let listener: TcpListener = match TcpListener::bind(("0.0.0.0", porta)).await {
+        Ok(listener) => listener,
+        Err(e) => panic!("Could not bind metrics endpoint on port {}: {}", porta, e),
+    };
+    log::info!("Metrics endpoint listening on port {}", porta);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(responder(stream));
+                }
+                Err(e) => log::warn!("Failed to accept metrics connection: {}", e),
+            }
+        }
+    });
+}
+
+/// HTTP mínimo: lê a linha de requisição e responde com as métricas ou 404.
+async fn responder(mut stream: TcpStream) {
+    let mut buffer: [u8; 1024] = [0; 1024];
+    let lidos: usize = match stream.read(&mut buffer).await {
+        Ok(lidos) => lidos,
+        Err(_) => return,
+    };
+    let requisicao = String::from_utf8_lossy(&buffer[..lidos]);
+    let caminho: Option<&str> = requisicao.lines().next().and_then(|linha| linha.strip_prefix("GET ")).and_then(|resto| resto.split(' ').next());
+
+    let (status, corpo) = match caminho {
+        Some("/metrics") => ("200 OK", prometheus()),
+        _ => ("404 Not Found", String::new()),
+    };
+    let resposta: String = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        corpo.len(),
+        corpo
+    );
+    if let Err(e) = stream.write_all(resposta.as_bytes()).await {
+        log::debug!("Failed to write metrics response: {}", e);
+    }
+    stream.shutdown().await.ok();
+}
+
+pub fn initialize_variables() -> MetricasVariables {
+    let porta: Option<u16> = env::var("METRICS_PORT")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| match p.trim().parse::<u16>() {
+            Ok(porta) => porta,
+            Err(_) => panic!("Invalid METRICS_PORT '{}', expected a port number", p),
+        });
+    MetricasVariables { porta }
+}
@@ -1,7 +1,9 @@
 
-use std::{env, error::Error, sync::OnceLock};
+use std::{env, sync::OnceLock};
 use bytes::Bytes;
-use minio::s3::{creds::{Provider, StaticProvider}, http::BaseUrl, types::S3Api, Client, ClientBuilder};
+use minio::s3::{creds::{Provider, StaticProvider}, error::{Error as MinioError, ErrorCode}, http::BaseUrl, types::S3Api, Client, ClientBuilder};
+
+use crate::falha::Falha;
 
 pub struct MinioVariables {
     pub endpoint: String,
@@ -31,16 +33,31 @@ pub fn init_client(variables: &MinioVariables) -> &'static Client {
     })
 }
 
-pub async fn download_object(object: &str, bucket_name: &str) -> Result<Bytes, Box<dyn Error>> {
+pub async fn download_object(object: &str, bucket_name: &str) -> Result<Bytes, Falha> {
     let client: &Client = CLIENT.get().expect("Client not initialized");
 
-    let resp: minio::s3::response::GetObjectResponse = client.get_object(bucket_name, object).send().await?;
+    let resp: minio::s3::response::GetObjectResponse = client.get_object(bucket_name, object).send().await
+        .map_err(classificar_erro)?;
 
-    let bytes: Bytes = resp.content.to_segmented_bytes().await?.to_bytes();
+    let bytes: Bytes = resp.content.to_segmented_bytes().await
+        .map_err(|e| Falha::transitoria("storage_unavailable", e))?
+        .to_bytes();
 
     Ok(bytes)
 }
 
+/// Objeto ou bucket inexistente e acesso negado não se resolvem sozinhos; o resto é tratado como instabilidade.
+fn classificar_erro(e: MinioError) -> Falha {
+    match &e {
+        MinioError::S3Error(resp) => match resp.code {
+            ErrorCode::NoSuchKey | ErrorCode::NoSuchBucket | ErrorCode::ResourceNotFound => Falha::revisao_manual("object_not_found", e),
+            ErrorCode::AccessDenied => Falha::revisao_manual("storage_access_denied", e),
+            _ => Falha::transitoria("storage_unavailable", e),
+        },
+        _ => Falha::transitoria("storage_unavailable", e),
+    }
+}
+
 
 pub fn initialize_variables() -> MinioVariables {
 
@@ -2,12 +2,11 @@
 use std::error::Error;
 
 use quick_xml::{
-    Reader,
     events::{Event},
 };
 use serde::Serialize;
 
-use crate::nfe::common::{ParseError, read_text};
+use crate::nfe::{common::{ParseError, XmlReader, read_text}, documento::normalizar_documento};
 
 #[derive(Debug, Default, Serialize)]
 pub struct Agropecuario {
@@ -41,7 +40,7 @@ pub struct GuiaTransito {
     pub nGuia: String,
 }
 
-pub fn parse_agropecuario(reader: &mut Reader<&[u8]>) -> Result<Agropecuario, Box<dyn Error>> {
+pub fn parse_agropecuario(reader: &mut XmlReader) -> Result<Agropecuario, Box<dyn Error>> {
     let mut agro: Agropecuario = Agropecuario::default();
 
     loop {
@@ -67,7 +66,7 @@ pub fn parse_agropecuario(reader: &mut Reader<&[u8]>) -> Result<Agropecuario, Bo
     }
 }
 
-fn parse_defensivo(reader: &mut Reader<&[u8]>) -> Result<Defensivo, Box<dyn Error>> {
+fn parse_defensivo(reader: &mut XmlReader) -> Result<Defensivo, Box<dyn Error>> {
     let mut def: Defensivo = Defensivo::default();
 
     loop {
@@ -76,7 +75,7 @@ fn parse_defensivo(reader: &mut Reader<&[u8]>) -> Result<Defensivo, Box<dyn Erro
                 let txt: String = read_text(reader, &e)?;
                 match e.name().as_ref() {
                     b"nReceituario" => def.nReceituario = txt,
-                    b"CPFRespTec" => def.CPFRespTec = txt,
+                    b"CPFRespTec" => def.CPFRespTec = normalizar_documento(txt),
                     _ => (),
                 }
             }
@@ -88,7 +87,7 @@ fn parse_defensivo(reader: &mut Reader<&[u8]>) -> Result<Defensivo, Box<dyn Erro
 }
 
 
-fn parse_guiaTransito(reader: &mut Reader<&[u8]>) -> Result<GuiaTransito, Box<dyn Error>> {
+fn parse_guiaTransito(reader: &mut XmlReader) -> Result<GuiaTransito, Box<dyn Error>> {
     let mut guia: GuiaTransito = GuiaTransito::default();
 
     loop {
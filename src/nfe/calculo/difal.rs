@@ -0,0 +1,130 @@
+#![allow(non_snake_case)]
+use rust_decimal::Decimal;
+
+use crate::nfe::{
+    calculo::{arredondar, percentual},
+    impostos::icms_uf_dest::ICMSUFDest,
+};
+
+/// Método de cálculo do DIFAL adotado pela UF de destino.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetodoDifal {
+    /// DIFAL = vBCUFDest × (alíquota interna − interestadual).
+    BaseUnica,
+    /// A base de destino é recomposta "por dentro": (vBC − ICMS origem) / (1 − alíquota interna com FCP).
+    BaseDupla,
+}
+
+/// Valores do grupo ICMSUFDest recalculados.
+#[derive(Debug, Default)]
+pub struct ValoresDifal {
+    pub vBCUFDest: Decimal,
+    pub vBCFCPUFDest: Decimal,
+    pub vFCPUFDest: Decimal,
+    pub vICMSUFDest: Decimal,
+    pub vICMSUFRemet: Decimal,
+}
+
+/// `vBCOrigem` é a base do ICMS próprio do item, usada apenas na base dupla.
+pub fn calcular_difal(g: &ICMSUFDest, pICMSInter: Decimal, vBCOrigem: Decimal, metodo: MetodoDifal) -> ValoresDifal {
+    let pFCP: Decimal = g.pFCPUFDest.unwrap_or_default();
+
+    let (vBCUFDest, difal) = match metodo {
+        MetodoDifal::BaseUnica => {
+            let difal = arredondar(percentual(g.vBCUFDest, g.pICMSUFDest - pICMSInter));
+            (g.vBCUFDest, difal)
+        }
+        MetodoDifal::BaseDupla => {
+            let icms_origem: Decimal = arredondar(percentual(vBCOrigem, pICMSInter));
+            let divisor: Decimal = Decimal::ONE - (g.pICMSUFDest + pFCP) / Decimal::ONE_HUNDRED;
+            let base: Decimal = arredondar((vBCOrigem - icms_origem) / divisor);
+            let difal = arredondar(percentual(base, g.pICMSUFDest)) - icms_origem;
+            (base, difal)
+        }
+    };
+    let difal: Decimal = difal.max(Decimal::ZERO);
+
+    // A base do FCP é a mesma do DIFAL nos dois métodos.
+    let vBCFCPUFDest: Decimal = vBCUFDest;
+
+    // Partilha: desde 2019 o pICMSInterPart é 100% para a UF de destino
+    let vICMSUFDest: Decimal = arredondar(percentual(difal, g.pICMSInterPart));
+
+    ValoresDifal {
+        vBCUFDest,
+        vBCFCPUFDest,
+        vFCPUFDest: arredondar(percentual(vBCFCPUFDest, pFCP)),
+        vICMSUFDest,
+        vICMSUFRemet: difal - vICMSUFDest,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(valor: &str) -> Decimal {
+        valor.parse().unwrap()
+    }
+
+    /// Venda interestadual a consumidor final: interna de 18% com FCP de 2% e partilha integral.
+    fn grupo(vBCUFDest: &str, pFCPUFDest: Option<&str>) -> ICMSUFDest {
+        ICMSUFDest {
+            vBCUFDest: d(vBCUFDest),
+            pFCPUFDest: pFCPUFDest.map(d),
+            pICMSUFDest: d("18.00"),
+            pICMSInter: "12.00".to_string(),
+            pICMSInterPart: d("100.00"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn base_unica_com_fcp() {
+        let v: ValoresDifal = calcular_difal(&grupo("1000.00", Some("2.00")), d("12.00"), d("1000.00"), MetodoDifal::BaseUnica);
+        assert_eq!(v.vBCUFDest, d("1000.00"));
+        assert_eq!(v.vBCFCPUFDest, d("1000.00"));
+        assert_eq!(v.vICMSUFDest, d("60.00"));
+        assert_eq!(v.vFCPUFDest, d("20.00"));
+        assert_eq!(v.vICMSUFRemet, Decimal::ZERO);
+    }
+
+    #[test]
+    fn base_dupla_recompoe_a_base_com_fcp() {
+        // (1000 − 120) / (1 − 0,20) = 1100; DIFAL = 1100 × 18% − 120 = 78.
+        let v: ValoresDifal = calcular_difal(&grupo("1000.00", Some("2.00")), d("12.00"), d("1000.00"), MetodoDifal::BaseDupla);
+        assert_eq!(v.vBCUFDest, d("1100.00"));
+        assert_eq!(v.vBCFCPUFDest, d("1100.00"));
+        assert_eq!(v.vICMSUFDest, d("78.00"));
+        assert_eq!(v.vFCPUFDest, d("22.00"));
+    }
+
+    #[test]
+    fn base_dupla_sem_fcp() {
+        // (1000 − 120) / 0,82 = 1073,17; DIFAL = 193,17 − 120.
+        let v: ValoresDifal = calcular_difal(&grupo("1000.00", None), d("12.00"), d("1000.00"), MetodoDifal::BaseDupla);
+        assert_eq!(v.vBCUFDest, d("1073.17"));
+        assert_eq!(v.vICMSUFDest, d("73.17"));
+        assert_eq!(v.vFCPUFDest, Decimal::ZERO);
+    }
+
+    #[test]
+    fn partilha_divide_o_difal_entre_as_ufs() {
+        let mut g: ICMSUFDest = grupo("1000.00", None);
+        g.pICMSInterPart = d("60.00");
+        let v: ValoresDifal = calcular_difal(&g, d("12.00"), d("1000.00"), MetodoDifal::BaseUnica);
+        assert_eq!(v.vICMSUFDest, d("36.00"));
+        assert_eq!(v.vICMSUFRemet, d("24.00"));
+    }
+
+    #[test]
+    fn difal_nao_fica_negativo() {
+        let v: ValoresDifal = calcular_difal(&grupo("1000.00", None), d("18.00"), d("1000.00"), MetodoDifal::BaseUnica);
+        assert_eq!(v.vICMSUFDest, Decimal::ZERO);
+        let mut g: ICMSUFDest = grupo("1000.00", None);
+        g.pICMSUFDest = d("7.00");
+        let v: ValoresDifal = calcular_difal(&g, d("12.00"), d("1000.00"), MetodoDifal::BaseUnica);
+        assert_eq!(v.vICMSUFDest, Decimal::ZERO);
+        assert_eq!(v.vICMSUFRemet, Decimal::ZERO);
+    }
+}
@@ -0,0 +1,205 @@
+#![allow(non_snake_case)]
+use rust_decimal::Decimal;
+
+use crate::nfe::{
+    calculo::{arredondar, percentual},
+    impostos::{
+        cibs::{TCIBS, TCredPres, TDevTrib, TDif, TRed, ValorCredPres},
+        monofasia::TMonofasia,
+    },
+};
+
+/// Valores recalculados de um grupo gIBSCBS.
+#[derive(Debug, Default)]
+pub struct ValoresIBSCBS {
+    pub vIBSUF: Tributo,
+    pub vIBSMun: Tributo,
+    pub vCBS: Tributo,
+    pub vIBS: Decimal,
+    pub vIBSCredPres: Option<Decimal>,
+    pub vCBSCredPres: Option<Decimal>,
+}
+
+/// Valor de um tributo (IBS UF, IBS Mun ou CBS) e as parcelas que o compõem.
+#[derive(Debug, Default)]
+pub struct Tributo {
+    /// Alíquota efetiva, após a redução de gRed.
+    pub pAliqEfet: Decimal,
+    pub vDif: Decimal,
+    pub valor: Decimal,
+}
+
+/// Valores recalculados de um grupo gIBSCBSMono.
+#[derive(Debug, Default)]
+pub struct ValoresMonofasia {
+    pub vIBSMono: Decimal,
+    pub vCBSMono: Decimal,
+    pub vIBSMonoReten: Decimal,
+    pub vCBSMonoReten: Decimal,
+    pub vIBSMonoRet: Decimal,
+    pub vCBSMonoRet: Decimal,
+    pub vIBSMonoDif: Decimal,
+    pub vCBSMonoDif: Decimal,
+    pub vTotIBSMonoItem: Decimal,
+    pub vTotCBSMonoItem: Decimal,
+}
+
+/// vTributo = vBC × alíquota efetiva − diferimento − devolução de tributos.
+pub fn calcular_cibs(g: &TCIBS) -> ValoresIBSCBS {
+    let uf = &g.gIBSUF;
+    let mun = &g.gIBSMun;
+    let cbs = &g.gCBS;
+
+    let vIBSUF: Tributo = calcular_tributo(g.vBC, uf.pIBSUF, &uf.gRed, &uf.gDif, &uf.gDevTrib);
+    let vIBSMun: Tributo = calcular_tributo(g.vBC, mun.pIBSMun, &mun.gRed, &mun.gDif, &mun.gDevTrib);
+    let vCBS: Tributo = calcular_tributo(g.vBC, cbs.pCBS, &cbs.gRed, &cbs.gDif, &cbs.gDevTrib);
+
+    ValoresIBSCBS {
+        vIBS: vIBSUF.valor + vIBSMun.valor,
+        vIBSCredPres: g.gIBSCredPres.as_ref().map(|c| calcular_cred_pres(g.vBC, c)),
+        vCBSCredPres: g.gCBSCredPres.as_ref().map(|c| calcular_cred_pres(g.vBC, c)),
+        vIBSUF,
+        vIBSMun,
+        vCBS,
+    }
+}
+
+fn calcular_tributo(vBC: Decimal, aliquota: Decimal, gRed: &Option<TRed>, gDif: &Option<TDif>, gDevTrib: &Option<TDevTrib>) -> Tributo {
+    let pAliqEfet: Decimal = match gRed {
+        Some(red) => aliquota * (Decimal::ONE_HUNDRED - red.pRedAliq) / Decimal::ONE_HUNDRED,
+        None => aliquota,
+    };
+    let bruto: Decimal = arredondar(percentual(vBC, pAliqEfet));
+    let vDif: Decimal = gDif.as_ref().map(|d| arredondar(percentual(bruto, d.pDif))).unwrap_or_default();
+    let vDevTrib: Decimal = gDevTrib.as_ref().map(|d| d.vDevTrib).unwrap_or_default();
+
+    Tributo {
+        pAliqEfet,
+        vDif,
+        valor: bruto - vDif - vDevTrib,
+    }
+}
+
+fn calcular_cred_pres(vBC: Decimal, cred: &TCredPres) -> Decimal {
+    arredondar(percentual(vBC, cred.pCredPres))
+}
+
+/// Valor informado no crédito presumido, seja vCredPres ou vCredPresCondSus.
+pub fn get_valor_cred_pres(cred: &TCredPres) -> Decimal {
+    match cred.valor {
+        ValorCredPres::vCredPres(v) | ValorCredPres::vCredPresCondSus(v) => v,
+    }
+}
+
+/// Monofásico: quantidade × ad rem, com o diferimento aplicado sobre o padrão.
+/// vTotXMonoItem = vXMono + vXMonoReten − vXMonoDif.
+pub fn calcular_monofasia(m: &TMonofasia) -> ValoresMonofasia {
+    let mut v: ValoresMonofasia = ValoresMonofasia::default();
+
+    if let Some(p) = &m.gMonoPadrao {
+        v.vIBSMono = arredondar(p.qBCMono * p.adRemIBS);
+        v.vCBSMono = arredondar(p.qBCMono * p.adRemCBS);
+    }
+    if let Some(r) = &m.gMonoReten {
+        v.vIBSMonoReten = arredondar(r.qBCMonoReten * r.adRemIBSReten);
+        v.vCBSMonoReten = arredondar(r.qBCMonoReten * r.adRemCBSReten);
+    }
+    if let Some(r) = &m.gMonoRet {
+        v.vIBSMonoRet = arredondar(r.qBCMonoRet * r.adRemIBSRet);
+        v.vCBSMonoRet = arredondar(r.qBCMonoRet * r.adRemCBSRet);
+    }
+    if let Some(d) = &m.gMonoDif {
+        v.vIBSMonoDif = arredondar(percentual(v.vIBSMono, d.pDifIBS));
+        v.vCBSMonoDif = arredondar(percentual(v.vCBSMono, d.pDifCBS));
+    }
+
+    v.vTotIBSMonoItem = v.vIBSMono + v.vIBSMonoReten - v.vIBSMonoDif;
+    v.vTotCBSMonoItem = v.vCBSMono + v.vCBSMonoReten - v.vCBSMonoDif;
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(valor: &str) -> Decimal {
+        valor.parse().unwrap()
+    }
+
+    /// Alíquotas do ano de teste de 2026: CBS 0,9% e IBS UF 0,1%.
+    fn grupo(vBC: &str) -> TCIBS {
+        let mut g: TCIBS = TCIBS { vBC: d(vBC), ..Default::default() };
+        g.gIBSUF.pIBSUF = d("0.10");
+        g.gCBS.pCBS = d("0.90");
+        g
+    }
+
+    #[test]
+    fn calcula_sem_reducao() {
+        let v: ValoresIBSCBS = calcular_cibs(&grupo("1000.00"));
+        assert_eq!(v.vIBSUF.valor, d("1.00"));
+        assert_eq!(v.vIBSMun.valor, Decimal::ZERO);
+        assert_eq!(v.vIBS, d("1.00"));
+        assert_eq!(v.vCBS.valor, d("9.00"));
+        assert_eq!(v.vIBSCredPres, None);
+    }
+
+    #[test]
+    fn aplica_reducao_de_aliquota() {
+        let mut g: TCIBS = grupo("1000.00");
+        g.gCBS.gRed = Some(TRed { pRedAliq: d("60.00"), pAliqEfet: d("0.36") });
+
+        let v: ValoresIBSCBS = calcular_cibs(&g);
+        assert_eq!(v.vCBS.pAliqEfet, d("0.36"));
+        assert_eq!(v.vCBS.valor, d("3.60"));
+        // A redução da CBS não afeta o IBS.
+        assert_eq!(v.vIBSUF.pAliqEfet, d("0.10"));
+        assert_eq!(v.vIBSUF.valor, d("1.00"));
+    }
+
+    #[test]
+    fn desconta_diferimento_do_valor_bruto() {
+        let mut g: TCIBS = grupo("1000.00");
+        g.gIBSUF.gDif = Some(TDif { pDif: d("30.00"), vDif: d("0.30") });
+        g.gCBS.gDif = Some(TDif { pDif: d("100.00"), vDif: d("9.00") });
+
+        let v: ValoresIBSCBS = calcular_cibs(&g);
+        assert_eq!(v.vIBSUF.vDif, d("0.30"));
+        assert_eq!(v.vIBSUF.valor, d("0.70"));
+        assert_eq!(v.vCBS.vDif, d("9.00"));
+        assert_eq!(v.vCBS.valor, Decimal::ZERO);
+    }
+
+    #[test]
+    fn diferimento_incide_sobre_a_aliquota_reduzida() {
+        let mut g: TCIBS = grupo("1000.00");
+        g.gCBS.gRed = Some(TRed { pRedAliq: d("60.00"), pAliqEfet: d("0.36") });
+        g.gCBS.gDif = Some(TDif { pDif: d("50.00"), vDif: d("1.80") });
+
+        let v: ValoresIBSCBS = calcular_cibs(&g);
+        assert_eq!(v.vCBS.vDif, d("1.80"));
+        assert_eq!(v.vCBS.valor, d("1.80"));
+    }
+
+    #[test]
+    fn calcula_credito_presumido_sobre_a_base() {
+        let mut g: TCIBS = grupo("1000.00");
+        g.gIBSCredPres = Some(TCredPres {
+            cCredPres: "01".to_string(),
+            pCredPres: d("1.50"),
+            valor: ValorCredPres::vCredPres(d("15.00")),
+        });
+        g.gCBSCredPres = Some(TCredPres {
+            cCredPres: "01".to_string(),
+            pCredPres: d("2.25"),
+            valor: ValorCredPres::vCredPresCondSus(d("22.50")),
+        });
+
+        let v: ValoresIBSCBS = calcular_cibs(&g);
+        assert_eq!(v.vIBSCredPres, Some(d("15.00")));
+        assert_eq!(v.vCBSCredPres, Some(d("22.50")));
+        assert_eq!(get_valor_cred_pres(g.gCBSCredPres.as_ref().unwrap()), d("22.50"));
+        // O crédito presumido não reduz o tributo do item.
+        assert_eq!(v.vCBS.valor, d("9.00"));
+    }
+}
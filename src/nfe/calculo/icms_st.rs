@@ -0,0 +1,152 @@
+#![allow(non_snake_case)]
+use rust_decimal::Decimal;
+
+use crate::nfe::{
+    calculo::{arredondar, percentual},
+    codigos::ModBCST,
+    impostos::icms::Icms,
+};
+
+/// Valores de ICMS-ST recalculados. `None` quando o dado necessário não está no XML.
+#[derive(Debug, Default)]
+pub struct ValoresST {
+    /// Recalculada apenas para MVA e valor da operação; nas demais modalidades a base informada é usada.
+    pub vBCST: Option<Decimal>,
+    /// Depende do ICMS próprio, ausente nos grupos do Simples Nacional.
+    pub vICMSST: Option<Decimal>,
+    pub vFCPST: Option<Decimal>,
+}
+
+/// `valor_item` = vProd + vFrete + vSeg + vOutro − vDesc + vIPI.
+///
+/// Base MVA: valor_item × (1 + pMVAST) × (1 − pRedBCST).
+/// vICMSST = vBCST × pICMSST − ICMS próprio; vFCPST = vBCFCPST × pFCPST − FCP próprio.
+///
+/// Na pauta e no preço tabelado ou listas (modBCST 0 a 3 e 5) a base vem de valores publicados
+/// pela UF, fora do XML: o vBCST informado é aceito sem conferência e apenas o imposto é recalculado.
+pub fn calcular_icms_st(icms: &Icms, valor_item: Decimal) -> Option<ValoresST> {
+    let modBCST: &ModBCST = icms.modBCST.as_ref()?;
+    let pICMSST: Decimal = icms.pICMSST?;
+    let reducao: Decimal = Decimal::ONE - icms.pRedBCST.unwrap_or_default() / Decimal::ONE_HUNDRED;
+
+    let vBCST: Option<Decimal> = match modBCST {
+        ModBCST::Mva => {
+            let mva: Decimal = Decimal::ONE + icms.pMVAST.unwrap_or_default() / Decimal::ONE_HUNDRED;
+            Some(arredondar(valor_item * mva * reducao))
+        }
+        ModBCST::ValorOperacao => Some(arredondar(valor_item * reducao)),
+        _ => None,
+    };
+    let base: Decimal = vBCST.or(icms.vBCST)?;
+
+    let icms_proprio: Option<Decimal> = match (&icms.CSOSN, icms.vICMS) {
+        (_, Some(vICMS)) => Some(vICMS),
+        (Some(_), None) => None,
+        (None, None) => Some(Decimal::ZERO),
+    };
+    let vICMSST: Option<Decimal> =
+        icms_proprio.map(|proprio| (arredondar(percentual(base, pICMSST)) - proprio).max(Decimal::ZERO));
+
+    let vFCPST: Option<Decimal> = icms.pFCPST.map(|pFCPST| {
+        let vBCFCPST: Decimal = icms.vBCFCPST.unwrap_or(base);
+        (arredondar(percentual(vBCFCPST, pFCPST)) - icms.vFCP.unwrap_or_default()).max(Decimal::ZERO)
+    });
+
+    Some(ValoresST { vBCST, vICMSST, vFCPST })
+}
+
+/// MVA ajustada das operações interestaduais, em %:
+/// [(1 + MVA original) × (1 − alíquota interestadual) / (1 − alíquota interna)] − 1.
+pub fn mva_ajustada(mva_original: Decimal, pICMSInter: Decimal, pICMSIntra: Decimal) -> Decimal {
+    let cem: Decimal = Decimal::ONE_HUNDRED;
+    let fator: Decimal = (cem + mva_original) * (cem - pICMSInter) / (cem - pICMSIntra);
+    arredondar(fator - cem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(valor: &str) -> Decimal {
+        valor.parse().unwrap()
+    }
+
+    /// Item com ICMS próprio de 12% sobre 100,00 e ST com alíquota interna de 18%.
+    fn icms(modBCST: ModBCST) -> Icms {
+        Icms {
+            vBC: Some(d("100.00")),
+            pICMS: Some(d("12.00")),
+            vICMS: Some(d("12.00")),
+            modBCST: Some(modBCST),
+            pICMSST: Some(d("18.00")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn base_pela_mva() {
+        let mut g: Icms = icms(ModBCST::Mva);
+        g.pMVAST = Some(d("40.00"));
+        let v: ValoresST = calcular_icms_st(&g, d("100.00")).unwrap();
+        assert_eq!(v.vBCST, Some(d("140.00")));
+        // 140 × 18% − 12
+        assert_eq!(v.vICMSST, Some(d("13.20")));
+        assert_eq!(v.vFCPST, None);
+    }
+
+    #[test]
+    fn mva_ajustada_interestadual() {
+        // [(1,40 × 0,88) / 0,82] − 1 = 50,24%
+        let mva: Decimal = mva_ajustada(d("40.00"), d("12.00"), d("18.00"));
+        assert_eq!(mva, d("50.24"));
+        // Com alíquotas iguais a MVA não muda.
+        assert_eq!(mva_ajustada(d("40.00"), d("18.00"), d("18.00")), d("40.00"));
+
+        let mut g: Icms = icms(ModBCST::Mva);
+        g.pMVAST = Some(mva);
+        let v: ValoresST = calcular_icms_st(&g, d("100.00")).unwrap();
+        assert_eq!(v.vBCST, Some(d("150.24")));
+        assert_eq!(v.vICMSST, Some(d("15.04")));
+    }
+
+    #[test]
+    fn reducao_da_base_st() {
+        let mut g: Icms = icms(ModBCST::Mva);
+        g.pMVAST = Some(d("40.00"));
+        g.pRedBCST = Some(d("10.00"));
+        let v: ValoresST = calcular_icms_st(&g, d("100.00")).unwrap();
+        assert_eq!(v.vBCST, Some(d("126.00")));
+        assert_eq!(v.vICMSST, Some(d("10.68")));
+    }
+
+    #[test]
+    fn base_pelo_valor_da_operacao_com_fcp() {
+        let mut g: Icms = icms(ModBCST::ValorOperacao);
+        g.pFCPST = Some(d("2.00"));
+        let v: ValoresST = calcular_icms_st(&g, d("110.00")).unwrap();
+        assert_eq!(v.vBCST, Some(d("110.00")));
+        assert_eq!(v.vICMSST, Some(d("7.80")));
+        // Sem vBCFCPST, o FCP ST usa a base do ST.
+        assert_eq!(v.vFCPST, Some(d("2.20")));
+    }
+
+    #[test]
+    fn pauta_aceita_a_base_informada() {
+        let mut g: Icms = icms(ModBCST::Pauta);
+        g.vBCST = Some(d("150.00"));
+        let v: ValoresST = calcular_icms_st(&g, d("100.00")).unwrap();
+        assert_eq!(v.vBCST, None);
+        assert_eq!(v.vICMSST, Some(d("15.00")));
+    }
+
+    #[test]
+    fn simples_nacional_sem_icms_proprio_nao_recalcula_o_imposto() {
+        let mut g: Icms = icms(ModBCST::Mva);
+        g.pMVAST = Some(d("40.00"));
+        g.CSOSN = Some("202".to_string());
+        g.vICMS = None;
+        let v: ValoresST = calcular_icms_st(&g, d("100.00")).unwrap();
+        assert_eq!(v.vBCST, Some(d("140.00")));
+        assert_eq!(v.vICMSST, None);
+    }
+}
@@ -0,0 +1,15 @@
+pub mod difal;
+pub mod ibs_cbs;
+pub mod icms_st;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Arredonda valores monetários para 2 casas (meio para cima, como no leiaute da NF-e).
+pub fn arredondar(valor: Decimal) -> Decimal {
+    valor.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Aplica um percentual (`pX` do XML, em %) sobre o valor.
+pub fn percentual(valor: Decimal, p: Decimal) -> Decimal {
+    valor * p / Decimal::ONE_HUNDRED
+}
@@ -1,11 +1,11 @@
 #![allow(non_snake_case, non_camel_case_types)]
 use std::error::Error;
 
-use quick_xml::{events::{BytesStart, Event}, Reader};
+use quick_xml::events::{BytesStart, Event};
 use rust_decimal::Decimal;
 use serde::Serialize;
 
-use crate::nfe::common::{read_text, ParseError};
+use crate::nfe::common::{read_text, ParseError, XmlReader};
 
 #[derive(Debug, Default, Serialize)]
 pub struct Cana {
@@ -33,7 +33,7 @@ pub struct Deduc {
     pub vDed: Decimal,
 }
 
-pub fn parse_cana(reader: &mut Reader<&[u8]>) -> Result<Cana, Box<dyn Error>> {
+pub fn parse_cana(reader: &mut XmlReader) -> Result<Cana, Box<dyn Error>> {
     let mut cana: Cana = Cana::default();
 
     loop {
@@ -65,7 +65,7 @@ pub fn parse_cana(reader: &mut Reader<&[u8]>) -> Result<Cana, Box<dyn Error>> {
     }
 }
 
-fn parse_forDia(reader: &mut Reader<&[u8]>, e: &BytesStart) -> Result<ForDia, Box<dyn Error>> {
+fn parse_forDia(reader: &mut XmlReader, e: &BytesStart) -> Result<ForDia, Box<dyn Error>> {
     let mut f: ForDia = ForDia::default();
     
     let attr = e.try_get_attribute(b"dia")?
@@ -85,7 +85,7 @@ fn parse_forDia(reader: &mut Reader<&[u8]>, e: &BytesStart) -> Result<ForDia, Bo
     }
 }
 
-fn parse_deduc(reader: &mut Reader<&[u8]>) -> Result<Deduc, Box<dyn Error>> {
+fn parse_deduc(reader: &mut XmlReader) -> Result<Deduc, Box<dyn Error>> {
     let mut d: Deduc = Deduc::default();
 
     loop {
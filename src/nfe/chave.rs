@@ -0,0 +1,140 @@
+#![allow(non_snake_case)]
+use serde::Serialize;
+
+/// Chave de acesso de 44 posições decomposta em seus campos.
+///
+/// O CNPJ/CPF é mantido como texto para comportar o CNPJ alfanumérico.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ChaveAcesso {
+    pub chave: String,
+    pub cUF: u8,
+    pub AAMM: String,
+    pub CNPJCPF: String,
+    pub r#mod: u8,
+    pub serie: u16,
+    pub nNF: u32,
+    pub tpEmis: u8,
+    pub cNF: String,
+    pub cDV: u8,
+    pub dvValido: bool,
+}
+
+impl ChaveAcesso {
+    /// Decompõe a chave, aceitando os prefixos `NFe`/`ID` do atributo Id.
+    /// Retorna `None` quando a chave não tem o formato de 44 posições.
+    pub fn decompor(valor: &str) -> Option<ChaveAcesso> {
+        let chave: &str = valor.trim().trim_start_matches("NFe").trim_start_matches("ID");
+        if chave.len() != 44 || !chave.is_ascii() {
+            return None;
+        }
+
+        let numerico = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        let CNPJCPF: &str = &chave[6..20];
+        if !numerico(&chave[..6]) || !numerico(&chave[20..]) || !CNPJCPF.bytes().all(|b| b.is_ascii_digit() || b.is_ascii_uppercase()) {
+            return None;
+        }
+
+        let cDV: u8 = chave[43..].parse().ok()?;
+        Some(ChaveAcesso {
+            chave: chave.to_string(),
+            cUF: chave[0..2].parse().ok()?,
+            AAMM: chave[2..6].to_string(),
+            CNPJCPF: CNPJCPF.to_string(),
+            r#mod: chave[20..22].parse().ok()?,
+            serie: chave[22..25].parse().ok()?,
+            nNF: chave[25..34].parse().ok()?,
+            tpEmis: chave[34..35].parse().ok()?,
+            cNF: chave[35..43].to_string(),
+            cDV,
+            dvValido: calcular_dv(&chave[..43]) == cDV,
+        })
+    }
+}
+
+/// Dígito verificador módulo 11 (pesos 2 a 9 da direita para a esquerda).
+///
+/// Cada caractere vale seu código ASCII menos 48, o que mantém o cálculo
+/// para chaves com CNPJ alfanumérico.
+pub fn calcular_dv(chave_sem_dv: &str) -> u8 {
+    let soma: u32 = chave_sem_dv
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| (b as u32 - 48) * (i as u32 % 8 + 2))
+        .sum();
+
+    match soma % 11 {
+        0 | 1 => 0,
+        resto => (11 - resto) as u8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Chave do exemplo do Manual de Orientação do Contribuinte.
+    const CHAVE_MOC: &str = "52060433009911002506550120000007800267301615";
+
+    #[test]
+    fn calcula_dv_de_chave_valida() {
+        assert_eq!(calcular_dv(&CHAVE_MOC[..43]), 5);
+    }
+
+    #[test]
+    fn resto_zero_ou_um_gera_dv_zero() {
+        // Soma com resto 0 e resto 1 na divisão por 11.
+        assert_eq!(calcular_dv("3524011234567800019555001000001234100001001"), 0);
+        assert_eq!(calcular_dv("3524011234567800019555001000001234100001007"), 0);
+    }
+
+    #[test]
+    fn decompoe_chave_valida() {
+        let chave: ChaveAcesso = ChaveAcesso::decompor(&format!("NFe{}", CHAVE_MOC)).unwrap();
+        assert_eq!(chave.chave, CHAVE_MOC);
+        assert_eq!(chave.cUF, 52);
+        assert_eq!(chave.AAMM, "0604");
+        assert_eq!(chave.CNPJCPF, "33009911002506");
+        assert_eq!(chave.r#mod, 55);
+        assert_eq!(chave.serie, 12);
+        assert_eq!(chave.nNF, 780);
+        assert_eq!(chave.tpEmis, 0);
+        assert_eq!(chave.cNF, "26730161");
+        assert_eq!(chave.cDV, 5);
+        assert!(chave.dvValido);
+    }
+
+    #[test]
+    fn decompoe_chave_com_cnpj_alfanumerico() {
+        let chave: ChaveAcesso = ChaveAcesso::decompor("35240112ABC34501DE35550010000012341000012340").unwrap();
+        assert_eq!(chave.CNPJCPF, "12ABC34501DE35");
+        assert!(chave.dvValido);
+    }
+
+    #[test]
+    fn detecta_chave_adulterada() {
+        // DV trocado.
+        let chave: ChaveAcesso = ChaveAcesso::decompor("52060433009911002506550120000007800267301614").unwrap();
+        assert!(!chave.dvValido);
+        // nNF alterado, DV original.
+        let chave: ChaveAcesso = ChaveAcesso::decompor("52060433009911002506550120000007810267301615").unwrap();
+        assert!(!chave.dvValido);
+    }
+
+    #[test]
+    fn rejeita_tamanho_invalido() {
+        assert!(ChaveAcesso::decompor(&CHAVE_MOC[..43]).is_none());
+        assert!(ChaveAcesso::decompor(&format!("{}0", CHAVE_MOC)).is_none());
+        assert!(ChaveAcesso::decompor("").is_none());
+    }
+
+    #[test]
+    fn rejeita_caracteres_nao_numericos() {
+        // Letra fora da posição do CNPJ.
+        assert!(ChaveAcesso::decompor("52060433009911002506550120000007800267301A15").is_none());
+        // Minúscula no CNPJ.
+        assert!(ChaveAcesso::decompor("5206043300991100250a550120000007800267301615").is_none());
+        // Caractere não ASCII.
+        assert!(ChaveAcesso::decompor("520604330099110025065501200000078002673016é").is_none());
+    }
+}
@@ -0,0 +1,192 @@
+#![allow(non_snake_case)]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    falha::Falha,
+    nfe::eventos::evento::{Evento, EventoJson},
+    nfes::NFe,
+};
+
+/// cStat do protocolo de autorização de uso.
+const CSTAT_AUTORIZADA: [&str; 2] = ["100", "150"];
+/// cStat de uso denegado.
+const CSTAT_DENEGADA: [&str; 5] = ["110", "205", "301", "302", "303"];
+/// cStat de evento registrado (vinculado ou não à NF-e, inclusive cancelamento fora do prazo).
+const CSTAT_EVENTO_REGISTRADO: [&str; 3] = ["135", "136", "155"];
+
+const EVENTO_CCE: &str = "110110";
+const EVENTOS_CANCELAMENTO: [&str; 2] = ["110111", "110112"];
+const EVENTOS_MANIFESTACAO: [&str; 4] = ["210200", "210210", "210220", "210240"];
+
+/// Fato que altera a situação de uma NF-e: protocolo de autorização ou evento registrado.
+#[derive(Debug, Clone, Serialize)]
+pub struct Ocorrencia {
+    pub chave: String,
+    pub tipo: TipoOcorrencia,
+    pub nProt: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum TipoOcorrencia {
+    Autorizacao,
+    Denegacao,
+    Cancelamento,
+    CartaCorrecao { nSeqEvento: u32 },
+    Manifestacao { tpEvento: String },
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Situacao {
+    /// Há eventos registrados, mas o protocolo da NF-e ainda não foi recebido.
+    #[default]
+    Desconhecida,
+    Autorizada,
+    Denegada,
+    Cancelada,
+}
+
+/// Situação projetada de uma NF-e a partir das ocorrências recebidas, em qualquer ordem.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EstadoDocumento {
+    pub situacao: Situacao,
+    pub nProt: Option<String>,
+    pub nProtCancelamento: Option<String>,
+    /// Último nSeqEvento de CC-e aplicado.
+    pub nSeqCCe: Option<u32>,
+    /// tpEvento da última manifestação do destinatário.
+    pub manifestacao: Option<String>,
+}
+
+impl EstadoDocumento {
+    /// O cancelamento prevalece sobre uma autorização recebida depois dele, e uma denegação
+    /// de NF-e já cancelada é recusada. Recusa uma CC-e com nSeqEvento anterior à última aplicada; a mesma sequência é ignorada.
+    pub fn aplicar(&mut self, ocorrencia: &Ocorrencia) -> Result<(), Falha> {
+        match &ocorrencia.tipo {
+            TipoOcorrencia::Autorizacao => {
+                self.nProt = ocorrencia.nProt.clone().or(self.nProt.take());
+                if self.situacao == Situacao::Desconhecida {
+                    self.situacao = Situacao::Autorizada;
+                }
+            }
+            TipoOcorrencia::Denegacao => {
+                if self.situacao == Situacao::Cancelada {
+                    return Err(Falha::revisao_manual(
+                        "lifecycle_invalid_transition",
+                        format!("Denegação da chave {} recebida após o cancelamento", ocorrencia.chave),
+                    ));
+                }
+                self.nProt = ocorrencia.nProt.clone().or(self.nProt.take());
+                self.situacao = Situacao::Denegada;
+            }
+            TipoOcorrencia::Cancelamento => {
+                self.nProtCancelamento = ocorrencia.nProt.clone().or(self.nProtCancelamento.take());
+                self.situacao = Situacao::Cancelada;
+            }
+            TipoOcorrencia::CartaCorrecao { nSeqEvento } => {
+                if let Some(atual) = self.nSeqCCe
+                    && *nSeqEvento < atual
+                {
+                    return Err(Falha::revisao_manual(
+                        "lifecycle_out_of_order",
+                        format!("CC-e {} da chave {} é anterior à sequência {} já registrada", nSeqEvento, ocorrencia.chave, atual),
+                    ));
+                }
+                self.nSeqCCe = Some(*nSeqEvento);
+            }
+            TipoOcorrencia::Manifestacao { tpEvento } => self.manifestacao = Some(tpEvento.clone()),
+        }
+        Ok(())
+    }
+}
+
+/// Autorização ou denegação a partir do protNFe. NF-e sem protocolo não gera ocorrência.
+pub fn ocorrencias_nfe(nfe: &NFe) -> Vec<Ocorrencia> {
+    let Some(prot) = &nfe.protNFe else {
+        return Vec::new();
+    };
+    let tipo: TipoOcorrencia = match prot.cStat.as_str() {
+        c if CSTAT_AUTORIZADA.contains(&c) => TipoOcorrencia::Autorizacao,
+        c if CSTAT_DENEGADA.contains(&c) => TipoOcorrencia::Denegacao,
+        _ => return Vec::new(),
+    };
+    let chave: String = match prot.chNFe.is_empty() {
+        true => nfe.Id.trim_start_matches("NFe").to_string(),
+        false => prot.chNFe.clone(),
+    };
+    vec![Ocorrencia { chave, tipo, nProt: prot.nProt.clone() }]
+}
+
+/// Eventos com retorno de registro. Pedidos de evento sem retorno (`<evento>`) são ignorados.
+pub fn ocorrencias_eventos(evento_json: &EventoJson) -> Vec<Ocorrencia> {
+    evento_json
+        .eventos
+        .iter()
+        .filter_map(|evento| match evento {
+            Evento::procEventoNFe(proc) => get_ocorrencia(
+                &proc.retEvento.cStat,
+                &proc.evento.infEvento.chNFe,
+                &proc.evento.infEvento.tpEvento,
+                &proc.evento.infEvento.nSeqEvento,
+                proc.retEvento.nProt.clone(),
+            ),
+            Evento::retEnvEvento(ret) => get_ocorrencia(
+                &ret.cStat,
+                ret.chNFe.as_deref()?,
+                ret.tpEvento.as_deref()?,
+                ret.nSeqEvento.as_deref().unwrap_or("1"),
+                ret.nProt.clone(),
+            ),
+            Evento::evento(_) => None,
+        })
+        .collect()
+}
+
+fn get_ocorrencia(cStat: &str, chNFe: &str, tpEvento: &str, nSeqEvento: &str, nProt: Option<String>) -> Option<Ocorrencia> {
+    if !CSTAT_EVENTO_REGISTRADO.contains(&cStat) || chNFe.is_empty() {
+        return None;
+    }
+    let tipo: TipoOcorrencia = match tpEvento {
+        t if EVENTOS_CANCELAMENTO.contains(&t) => TipoOcorrencia::Cancelamento,
+        EVENTO_CCE => TipoOcorrencia::CartaCorrecao { nSeqEvento: nSeqEvento.trim().parse().ok()? },
+        t if EVENTOS_MANIFESTACAO.contains(&t) => TipoOcorrencia::Manifestacao { tpEvento: t.to_string() },
+        _ => return None,
+    };
+    Some(Ocorrencia { chave: chNFe.to_string(), tipo, nProt })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ocorrencia(tipo: TipoOcorrencia) -> Ocorrencia {
+        Ocorrencia { chave: "52060433009911002506550120000007800267301615".to_string(), tipo, nProt: None }
+    }
+
+    #[test]
+    fn cancelamento_prevalece_sobre_autorizacao_posterior() {
+        let mut estado: EstadoDocumento = EstadoDocumento::default();
+        estado.aplicar(&ocorrencia(TipoOcorrencia::Cancelamento)).unwrap();
+        estado.aplicar(&ocorrencia(TipoOcorrencia::Autorizacao)).unwrap();
+        assert_eq!(estado.situacao, Situacao::Cancelada);
+    }
+
+    #[test]
+    fn recusa_denegacao_apos_cancelamento() {
+        let mut estado: EstadoDocumento = EstadoDocumento::default();
+        estado.aplicar(&ocorrencia(TipoOcorrencia::Autorizacao)).unwrap();
+        estado.aplicar(&ocorrencia(TipoOcorrencia::Cancelamento)).unwrap();
+        let falha: Falha = estado.aplicar(&ocorrencia(TipoOcorrencia::Denegacao)).unwrap_err();
+        assert_eq!(falha.motivo, "lifecycle_invalid_transition");
+        assert_eq!(estado.situacao, Situacao::Cancelada);
+    }
+
+    #[test]
+    fn recusa_cce_fora_de_ordem() {
+        let mut estado: EstadoDocumento = EstadoDocumento::default();
+        estado.aplicar(&ocorrencia(TipoOcorrencia::CartaCorrecao { nSeqEvento: 2 })).unwrap();
+        // A mesma sequência é reentregue sem erro.
+        estado.aplicar(&ocorrencia(TipoOcorrencia::CartaCorrecao { nSeqEvento: 2 })).unwrap();
+        assert!(estado.aplicar(&ocorrencia(TipoOcorrencia::CartaCorrecao { nSeqEvento: 1 })).is_err());
+        assert_eq!(estado.nSeqCCe, Some(2));
+    }
+}
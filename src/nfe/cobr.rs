@@ -3,6 +3,8 @@
 use rust_decimal::Decimal;
 use serde::Serialize;
 
+use crate::nfe::data_hora::Data;
+
 #[derive(Debug, Default, Serialize)]
 pub struct Cobr {
     pub fat: Option<Fat>,
@@ -20,6 +22,6 @@ pub struct Fat {
 #[derive(Debug, Default, Serialize)]
 pub struct Dup {
     pub nDup: Option<String>,
-    pub dVenc: Option<String>,
+    pub dVenc: Option<Data>,
     pub vDup: Decimal,
 }
@@ -0,0 +1,231 @@
+use serde::{Serialize, Serializer};
+
+use crate::nfe::{common::ParseError, config::get_config};
+
+/// Campo codificado pela SEFAZ (tabelas do Manual de Orientação do Contribuinte).
+pub trait CodigoSefaz: Sized {
+    fn from_codigo(codigo: &str) -> Self;
+    fn codigo(&self) -> &str;
+    fn descricao(&self) -> Option<&'static str>;
+
+    fn is_desconhecido(&self) -> bool {
+        self.descricao().is_none()
+    }
+}
+
+/// Declara um enum de códigos SEFAZ com a variante `Desconhecido` para valores fora da tabela.
+///
+/// `numerico` mantém a serialização como número para campos que antes eram `u8`.
+macro_rules! codigo_sefaz {
+    ($(#[$meta:meta])* $nome:ident, numerico = $numerico:literal, { $($variante:ident = $codigo:literal => $desc:literal),+ $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum $nome {
+            $($variante,)+
+            Desconhecido(String),
+        }
+
+        impl CodigoSefaz for $nome {
+            fn from_codigo(codigo: &str) -> Self {
+                match codigo {
+                    $($codigo => Self::$variante,)+
+                    outro => Self::Desconhecido(outro.to_string()),
+                }
+            }
+
+            fn codigo(&self) -> &str {
+                match self {
+                    $(Self::$variante => $codigo,)+
+                    Self::Desconhecido(codigo) => codigo,
+                }
+            }
+
+            fn descricao(&self) -> Option<&'static str> {
+                match self {
+                    $(Self::$variante => Some($desc),)+
+                    Self::Desconhecido(_) => None,
+                }
+            }
+        }
+
+        impl Default for $nome {
+            fn default() -> Self {
+                Self::Desconhecido(String::new())
+            }
+        }
+
+        impl Serialize for $nome {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serialize_codigo(self.codigo(), $numerico, serializer)
+            }
+        }
+    };
+}
+
+fn serialize_codigo<S: Serializer>(codigo: &str, numerico: bool, serializer: S) -> Result<S::Ok, S::Error> {
+    match codigo.parse::<u8>() {
+        Ok(n) if numerico => serializer.serialize_u8(n),
+        _ => serializer.serialize_str(codigo),
+    }
+}
+
+/// Converte o texto do XML no enum correspondente. Fora do modo estrito, códigos
+/// desconhecidos são mantidos e reportados pela validação.
+pub fn parse_codigo<T: CodigoSefaz>(txt: String, campo: &str) -> Result<T, ParseError> {
+    let codigo: T = T::from_codigo(txt.trim());
+    if codigo.is_desconhecido() {
+        if get_config().strict {
+            return Err(ParseError::ValorInvalido(campo.to_string(), txt));
+        }
+        log::warn!("Código desconhecido em {}: '{}'", campo, txt);
+    }
+    Ok(codigo)
+}
+
+/// Descrição do código, apenas quando `PARSER_DESCRICOES` estiver habilitado.
+pub fn descricao<T: CodigoSefaz>(codigo: &T) -> Option<&'static str> {
+    if get_config().descricoes {
+        codigo.descricao()
+    } else {
+        None
+    }
+}
+
+codigo_sefaz!(
+    /// Modalidade do frete (modFrete).
+    ModFrete, numerico = false, {
+        Remetente = "0" => "Contratação do frete por conta do remetente (CIF)",
+        Destinatario = "1" => "Contratação do frete por conta do destinatário (FOB)",
+        Terceiros = "2" => "Contratação do frete por conta de terceiros",
+        ProprioRemetente = "3" => "Transporte próprio por conta do remetente",
+        ProprioDestinatario = "4" => "Transporte próprio por conta do destinatário",
+        SemFrete = "9" => "Sem ocorrência de transporte",
+    }
+);
+
+codigo_sefaz!(
+    /// Meio de pagamento (tPag).
+    TPag, numerico = false, {
+        Dinheiro = "01" => "Dinheiro",
+        Cheque = "02" => "Cheque",
+        CartaoCredito = "03" => "Cartão de Crédito",
+        CartaoDebito = "04" => "Cartão de Débito",
+        CartaoLoja = "05" => "Cartão da Loja (Private Label)",
+        ValeAlimentacao = "10" => "Vale Alimentação",
+        ValeRefeicao = "11" => "Vale Refeição",
+        ValePresente = "12" => "Vale Presente",
+        ValeCombustivel = "13" => "Vale Combustível",
+        DuplicataMercantil = "14" => "Duplicata Mercantil",
+        Boleto = "15" => "Boleto Bancário",
+        Deposito = "16" => "Depósito Bancário",
+        PixDinamico = "17" => "PIX",
+        Transferencia = "18" => "Transferência bancária, Carteira Digital",
+        Fidelidade = "19" => "Programa de fidelidade, Cashback, Crédito Virtual",
+        PixEstatico = "20" => "PIX Estático",
+        CreditoLoja = "21" => "Crédito em Loja",
+        EletronicoNaoInformado = "22" => "Pagamento Eletrônico não Informado - falha de hardware do sistema emissor",
+        SemPagamento = "90" => "Sem pagamento",
+        Outros = "99" => "Outros",
+    }
+);
+
+codigo_sefaz!(
+    /// Finalidade de emissão da NF-e (finNFe).
+    FinNFe, numerico = true, {
+        Normal = "1" => "NF-e normal",
+        Complementar = "2" => "NF-e complementar",
+        Ajuste = "3" => "NF-e de ajuste",
+        Devolucao = "4" => "Devolução de mercadoria",
+        Credito = "5" => "Nota de crédito",
+        Debito = "6" => "Nota de débito",
+    }
+);
+
+codigo_sefaz!(
+    /// Indicador de presença do comprador (indPres).
+    IndPres, numerico = true, {
+        NaoSeAplica = "0" => "Não se aplica",
+        Presencial = "1" => "Operação presencial",
+        Internet = "2" => "Operação não presencial, pela Internet",
+        Teleatendimento = "3" => "Operação não presencial, Teleatendimento",
+        EntregaDomicilio = "4" => "NFC-e em operação com entrega a domicílio",
+        PresencialForaEstabelecimento = "5" => "Operação presencial, fora do estabelecimento",
+        NaoPresencialOutros = "9" => "Operação não presencial, outros",
+    }
+);
+
+codigo_sefaz!(
+    /// Tipo de emissão (tpEmis).
+    TpEmis, numerico = true, {
+        Normal = "1" => "Emissão normal",
+        ContingenciaFsIa = "2" => "Contingência FS-IA",
+        ContingenciaScan = "3" => "Contingência SCAN",
+        ContingenciaEpec = "4" => "Contingência EPEC",
+        ContingenciaFsDa = "5" => "Contingência FS-DA",
+        ContingenciaSvcAn = "6" => "Contingência SVC-AN",
+        ContingenciaSvcRs = "7" => "Contingência SVC-RS",
+        ContingenciaOffline = "9" => "Contingência off-line da NFC-e",
+    }
+);
+
+codigo_sefaz!(
+    /// Código de Regime Tributário do emitente (CRT).
+    Crt, numerico = true, {
+        SimplesNacional = "1" => "Simples Nacional",
+        SimplesExcessoSublimite = "2" => "Simples Nacional - excesso de sublimite de receita bruta",
+        RegimeNormal = "3" => "Regime Normal",
+        Mei = "4" => "Simples Nacional - Microempreendedor Individual (MEI)",
+    }
+);
+
+codigo_sefaz!(
+    /// Indicador da IE do destinatário (indIEDest).
+    IndIEDest, numerico = true, {
+        Contribuinte = "1" => "Contribuinte ICMS",
+        ContribuinteIsento = "2" => "Contribuinte isento de Inscrição no cadastro de Contribuintes",
+        NaoContribuinte = "9" => "Não Contribuinte",
+    }
+);
+
+codigo_sefaz!(
+    /// Modalidade de determinação da BC do ICMS (modBC).
+    ModBC, numerico = false, {
+        Mva = "0" => "Margem Valor Agregado (%)",
+        Pauta = "1" => "Pauta (valor)",
+        PrecoTabelado = "2" => "Preço Tabelado Máximo (valor)",
+        ValorOperacao = "3" => "Valor da operação",
+    }
+);
+
+codigo_sefaz!(
+    /// Modalidade de determinação da BC do ICMS ST (modBCST).
+    ModBCST, numerico = false, {
+        PrecoTabelado = "0" => "Preço tabelado ou máximo sugerido",
+        ListaNegativa = "1" => "Lista Negativa (valor)",
+        ListaPositiva = "2" => "Lista Positiva (valor)",
+        ListaNeutra = "3" => "Lista Neutra (valor)",
+        Mva = "4" => "Margem Valor Agregado (%)",
+        Pauta = "5" => "Pauta (valor)",
+        ValorOperacao = "6" => "Valor da Operação",
+    }
+);
+
+codigo_sefaz!(
+    /// Motivo da desoneração do ICMS (motDesICMS / motDesICMSST).
+    MotDesICMS, numerico = false, {
+        Taxi = "1" => "Táxi",
+        DeficienteFisico = "2" => "Deficiente Físico",
+        ProdutorAgropecuario = "3" => "Produtor Agropecuário",
+        FrotistaLocadora = "4" => "Frotista/Locadora",
+        DiplomaticoConsular = "5" => "Diplomático/Consular",
+        AmazoniaOcidental = "6" => "Utilitários e Motocicletas da Amazônia Ocidental e Áreas de Livre Comércio",
+        Suframa = "7" => "SUFRAMA",
+        OrgaoPublico = "8" => "Venda a Órgão Público",
+        Outros = "9" => "Outros",
+        DeficienteCondutor = "10" => "Deficiente Condutor",
+        DeficienteNaoCondutor = "11" => "Deficiente Não Condutor",
+        FomentoAgropecuario = "12" => "Órgão de fomento e desenvolvimento agropecuário",
+        Olimpiadas = "16" => "Olimpíadas Rio 2016",
+        SolicitadoFisco = "90" => "Solicitado pelo Fisco",
+    }
+);
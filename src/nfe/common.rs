@@ -1,10 +1,95 @@
-use std::error::Error;
+use std::{borrow::Cow, error::Error};
 
-use quick_xml::{events::BytesStart, Reader};
+use quick_xml::{
+    events::{BytesStart, Event},
+    name::QName,
+    reader::Config,
+    Reader,
+};
 
+use crate::nfe::validacao::Diagnostico;
 
+/// Leitor XML que acompanha a pilha de elementos abertos, para informar o caminho do elemento
+/// em que o parse falhou (ex.: `nfeProc/NFe/infNFe/emit/enderEmit/UF`).
+pub struct XmlReader<'a> {
+    reader: Reader<&'a [u8]>,
+    pilha: Vec<String>,
+    /// O texto do elemento no topo da pilha já foi lido até o fechamento. O elemento só sai da
+    /// pilha no próximo evento, para que um erro ao converter o texto ainda aponte para ele.
+    texto_lido: bool,
+    /// Valores descartados durante o parse, fora do modo estrito. Vão para os `diagnosticos` do documento.
+    diagnosticos: Vec<Diagnostico>,
+}
+
+impl<'a> XmlReader<'a> {
+    pub fn from_reader(xml: &'a [u8]) -> Self {
+        XmlReader { reader: Reader::from_reader(xml), pilha: Vec::new(), texto_lido: false, diagnosticos: Vec::new() }
+    }
+
+    pub fn config_mut(&mut self) -> &mut Config {
+        self.reader.config_mut()
+    }
+
+    pub fn read_event(&mut self) -> quick_xml::Result<Event<'a>> {
+        if self.texto_lido {
+            self.pilha.pop();
+            self.texto_lido = false;
+        }
+        let evento: Event<'a> = self.reader.read_event()?;
+        match &evento {
+            Event::Start(e) => self.pilha.push(String::from_utf8_lossy(e.name().as_ref()).into_owned()),
+            Event::End(_) => {
+                self.pilha.pop();
+            }
+            _ => (),
+        }
+        Ok(evento)
+    }
+
+    pub fn read_text(&mut self, end: QName) -> quick_xml::Result<Cow<'a, str>> {
+        let texto: Cow<'a, str> = self.reader.read_text(end)?;
+        self.texto_lido = true;
+        Ok(texto)
+    }
 
-pub type XmlReader<'a> = Reader<&'a [u8]>;
+    /// Caminho do elemento atual, da raiz do documento até ele.
+    pub fn caminho(&self) -> String {
+        self.pilha.join("/")
+    }
+
+    pub fn diagnosticar(&mut self, diagnostico: Diagnostico) {
+        self.diagnosticos.push(diagnostico);
+    }
+
+    /// Retira os diagnósticos registrados até aqui.
+    pub fn coletar_diagnosticos(&mut self) -> Vec<Diagnostico> {
+        std::mem::take(&mut self.diagnosticos)
+    }
+
+    /// Associa o erro ao elemento em que o parse parou.
+    pub fn erro_no_caminho(&self, erro: Box<dyn Error>) -> Box<dyn Error> {
+        Box::new(ErroNoCaminho { caminho: self.caminho(), erro })
+    }
+}
+
+/// Erro do parse com o caminho do elemento em que ocorreu.
+#[derive(Debug)]
+pub struct ErroNoCaminho {
+    pub caminho: String,
+    pub erro: Box<dyn Error>,
+}
+
+impl Error for ErroNoCaminho {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.erro.as_ref())
+    }
+}
+
+impl std::fmt::Display for ErroNoCaminho {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.erro.fmt(f)
+    }
+}
 
 #[derive(Debug)]
 pub enum ParseError {
@@ -13,6 +98,7 @@ pub enum ParseError {
     CampoDesconhecido(String),
     UnexpectedEof(String),
     Xml(String),
+    ValorInvalido(String, String),
 }
 
 impl Error for ParseError {}
@@ -29,6 +115,9 @@ impl std::fmt::Display for ParseError {
             ParseError::UnexpectedEof(item) => {
                 write!(f, "Unexpected Eof while parsing {}", item)
             }
+            ParseError::ValorInvalido(campo, valor) => {
+                write!(f, "Valor inválido em {}: '{}'", campo, valor)
+            }
         }
     }
 }
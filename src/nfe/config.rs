@@ -0,0 +1,116 @@
+use std::{env, path::Path, sync::{Arc, OnceLock}};
+
+use crate::{nfe::{empresas::{CadastroArquivo, CadastroEmpresas}, tabelas::mva::TabelaMva}, nfes::UF};
+
+/// Opções do parser, lidas uma única vez das variáveis de ambiente.
+#[derive(Debug, Default, Clone)]
+pub struct ParserVariables {
+    /// Rejeita o documento ao encontrar valores inválidos, em vez de apenas reportá-los.
+    pub strict: bool,
+    /// Serializa a descrição dos campos codificados ao lado do código (ex.: `tPagDesc`).
+    pub descricoes: bool,
+    /// Confere o grupo ICMSTot com o somatório dos itens e o vNF com a fórmula oficial,
+    /// e recalcula IBS/CBS, DIFAL/FCP e ICMS-ST de cada item.
+    pub validar_totais: bool,
+    /// UFs de destino que calculam o DIFAL por base dupla.
+    pub difal_base_dupla: Vec<UF>,
+    /// MVA esperada por NCM/UF, usada na conferência do ICMS-ST.
+    pub tabela_mva: Option<TabelaMva>,
+    /// CNPJs/CPFs de cada `company_id`, usados para classificar a nota como emitida ou recebida.
+    pub empresas: Option<Arc<dyn CadastroEmpresas>>,
+    /// Diretório com as tabelas de referência oficiais, que substituem as amostras embutidas.
+    pub tabelas_dir: Option<String>,
+    /// Parses simultâneos no pool de CPU. `0` usa o número de núcleos disponíveis.
+    pub workers: usize,
+}
+
+static CONFIG: OnceLock<ParserVariables> = OnceLock::new();
+
+pub fn init_config(variables: ParserVariables) -> &'static ParserVariables {
+    CONFIG.get_or_init(|| variables)
+}
+
+/// Configuração atual do parser. Usa os valores padrão caso `init_config` não tenha sido chamado.
+pub fn get_config() -> &'static ParserVariables {
+    CONFIG.get_or_init(ParserVariables::default)
+}
+
+pub fn initialize_variables() -> ParserVariables {
+    ParserVariables {
+        strict: get_flag("PARSER_STRICT_MODE"),
+        descricoes: get_flag("PARSER_DESCRICOES"),
+        validar_totais: get_flag("PARSER_VALIDAR_TOTAIS"),
+        difal_base_dupla: get_ufs("PARSER_DIFAL_BASE_DUPLA"),
+        tabela_mva: get_tabela_mva("PARSER_MVA_CSV"),
+        empresas: get_cadastro_empresas("PARSER_EMPRESAS_CSV"),
+        tabelas_dir: get_diretorio("PARSER_TABELAS_DIR"),
+        workers: get_workers("PARSER_WORKERS"),
+    }
+}
+
+fn get_diretorio(var: &str) -> Option<String> {
+    let caminho: String = env::var(var).ok().filter(|c| !c.trim().is_empty())?;
+    if !Path::new(&caminho).is_dir() {
+        panic!("Invalid {}: {} is not a directory", var, caminho);
+    }
+    Some(caminho)
+}
+
+fn get_workers(var: &str) -> usize {
+    match env::var(var) {
+        Ok(v) if !v.trim().is_empty() => match v.trim().parse() {
+            Ok(n) => n,
+            Err(e) => panic!("Invalid {}: {}", var, e),
+        },
+        _ => 0,
+    }
+}
+
+#[inline]
+fn get_flag(var: &str) -> bool {
+    match env::var(var) {
+        Ok(v) => matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"),
+        Err(_) => false,
+    }
+}
+
+/// Lista de UFs separadas por vírgula. Siglas inválidas são ignoradas com aviso.
+fn get_ufs(var: &str) -> Vec<UF> {
+    let Ok(valor) = env::var(var) else {
+        return Vec::new();
+    };
+    valor
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| match UF::try_from(s.to_uppercase().as_str()) {
+            Ok(uf) => Some(uf),
+            Err(e) => {
+                log::warn!("{}: {}", var, e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn get_tabela_mva(var: &str) -> Option<TabelaMva> {
+    let caminho: String = env::var(var).ok().filter(|c| !c.trim().is_empty())?;
+    match TabelaMva::carregar(&caminho) {
+        Ok(tabela) => {
+            log::info!("Tabela de MVA carregada de {}", caminho);
+            Some(tabela)
+        }
+        Err(e) => panic!("Invalid MVA table {}: {}", caminho, e),
+    }
+}
+
+fn get_cadastro_empresas(var: &str) -> Option<Arc<dyn CadastroEmpresas>> {
+    let caminho: String = env::var(var).ok().filter(|c| !c.trim().is_empty())?;
+    match CadastroArquivo::carregar(&caminho) {
+        Ok(cadastro) => {
+            log::info!("Cadastro de empresas carregado de {}", caminho);
+            Some(Arc::new(cadastro))
+        }
+        Err(e) => panic!("Invalid company registry {}: {}", caminho, e),
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use time::{
+    format_description::{well_known::Rfc3339, BorrowedFormatItem},
+    macros::{format_description, offset},
+    Date, OffsetDateTime, PrimitiveDateTime, UtcOffset,
+};
+
+use crate::nfe::{common::ParseError, config::get_config};
+
+const FORMATO_SEM_OFFSET: &[BorrowedFormatItem<'static>] =
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]");
+const FORMATO_DATA: &[BorrowedFormatItem<'static>] = format_description!("[year]-[month]-[day]");
+
+/// Horário de Brasília, assumido quando o emissor não informa o offset.
+const OFFSET_PADRAO: UtcOffset = offset!(-3);
+
+/// Data e hora no formato `AAAA-MM-DDThh:mm:ssTZD`.
+///
+/// Serializada como `{"original": ..., "utc": ...}`, onde `utc` é `null` quando o valor
+/// original não pôde ser interpretado (apenas fora do modo estrito).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DataHora {
+    pub original: String,
+    pub valor: Option<OffsetDateTime>,
+}
+
+/// Data no formato `AAAA-MM-DD`, serializada como `{"original": ..., "iso": ...}`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Data {
+    pub original: String,
+    pub valor: Option<Date>,
+}
+
+impl DataHora {
+    pub fn utc(&self) -> Option<String> {
+        self.valor
+            .and_then(|v| v.to_offset(UtcOffset::UTC).format(&Rfc3339).ok())
+    }
+}
+
+impl Data {
+    pub fn iso(&self) -> Option<String> {
+        self.valor.and_then(|v| v.format(FORMATO_DATA).ok())
+    }
+}
+
+pub fn parse_data_hora(txt: String, campo: &str) -> Result<DataHora, ParseError> {
+    let valor: Option<OffsetDateTime> = OffsetDateTime::parse(txt.trim(), &Rfc3339)
+        .ok()
+        .or_else(|| {
+            PrimitiveDateTime::parse(txt.trim(), FORMATO_SEM_OFFSET)
+                .ok()
+                .map(|dt| dt.assume_offset(OFFSET_PADRAO))
+        });
+
+    if valor.is_none() {
+        rejeitar_invalido(campo, &txt)?;
+    }
+
+    Ok(DataHora { original: txt, valor })
+}
+
+pub fn parse_data(txt: String, campo: &str) -> Result<Data, ParseError> {
+    let valor: Option<Date> = Date::parse(txt.trim(), FORMATO_DATA).ok();
+
+    if valor.is_none() {
+        rejeitar_invalido(campo, &txt)?;
+    }
+
+    Ok(Data { original: txt, valor })
+}
+
+fn rejeitar_invalido(campo: &str, txt: &str) -> Result<(), ParseError> {
+    if get_config().strict {
+        return Err(ParseError::ValorInvalido(campo.to_string(), txt.to_string()));
+    }
+    log::warn!("Data inválida em {}: '{}'", campo, txt);
+    Ok(())
+}
+
+impl Serialize for DataHora {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("DataHora", 2)?;
+        s.serialize_field("original", &self.original)?;
+        s.serialize_field("utc", &self.utc())?;
+        s.end()
+    }
+}
+
+impl Serialize for Data {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Data", 2)?;
+        s.serialize_field("original", &self.original)?;
+        s.serialize_field("iso", &self.iso())?;
+        s.end()
+    }
+}
@@ -0,0 +1,127 @@
+use crate::nfe::chave::calcular_dv;
+
+/// Remove máscara e espaços de CNPJ/CPF (`12.345.678/0001-95` → `12345678000195`).
+///
+/// Letras são mantidas em maiúsculas por causa do CNPJ alfanumérico.
+pub fn normalizar_documento(txt: String) -> String {
+    if txt.bytes().all(|b| b.is_ascii_digit()) {
+        return txt;
+    }
+    txt.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// CNPJ numérico ou alfanumérico: 12 posições alfanuméricas seguidas de 2 dígitos verificadores.
+///
+/// O DV usa o mesmo módulo 11 da chave de acesso, com cada caractere valendo seu código ASCII menos 48.
+pub fn cnpj_valido(cnpj: &str) -> bool {
+    if cnpj.len() != 14 || !cnpj.is_ascii() {
+        return false;
+    }
+    let (base, dv) = cnpj.split_at(12);
+    if !base.bytes().all(|b| b.is_ascii_digit() || b.is_ascii_uppercase()) || !dv.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    if cnpj.bytes().all(|b| b == b'0') {
+        return false;
+    }
+
+    let dv1: u8 = calcular_dv(base);
+    let dv2: u8 = calcular_dv(&format!("{}{}", base, dv1));
+    dv == format!("{}{}", dv1, dv2)
+}
+
+pub fn cpf_valido(cpf: &str) -> bool {
+    if cpf.len() != 11 || !cpf.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let digitos: Vec<u32> = cpf.bytes().map(|b| (b - b'0') as u32).collect();
+    if digitos.iter().all(|&d| d == digitos[0]) {
+        return false;
+    }
+
+    let dv = |n: usize| -> u32 {
+        let soma: u32 = digitos[..n]
+            .iter()
+            .enumerate()
+            .map(|(i, d)| d * (n as u32 + 1 - i as u32))
+            .sum();
+        match soma % 11 {
+            0 | 1 => 0,
+            resto => 11 - resto,
+        }
+    };
+    dv(9) == digitos[9] && dv(10) == digitos[10]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aceita_cnpj_numerico() {
+        assert!(cnpj_valido("11222333000181"));
+        assert!(!cnpj_valido("11222333000182"));
+    }
+
+    #[test]
+    fn aceita_cnpj_alfanumerico() {
+        // Exemplo da Receita Federal para o CNPJ alfanumérico.
+        assert!(cnpj_valido("12ABC34501DE35"));
+        assert!(!cnpj_valido("12ABC34501DE36"));
+        // Letras só na raiz e na ordem; o DV é sempre numérico.
+        assert!(!cnpj_valido("12abc34501de35"));
+        assert!(!cnpj_valido("12ABC34501DEA5"));
+    }
+
+    #[test]
+    fn rejeita_cnpj_com_digitos_repetidos() {
+        for d in '0'..='9' {
+            assert!(!cnpj_valido(&d.to_string().repeat(14)), "{}", d);
+        }
+    }
+
+    #[test]
+    fn rejeita_cnpj_com_tamanho_invalido() {
+        assert!(!cnpj_valido("1122233300018"));
+        assert!(!cnpj_valido("112223330001811"));
+        assert!(!cnpj_valido(""));
+    }
+
+    #[test]
+    fn aceita_cpf_valido() {
+        assert!(cpf_valido("52998224725"));
+        assert!(!cpf_valido("52998224726"));
+    }
+
+    #[test]
+    fn rejeita_cpf_com_digitos_repetidos() {
+        // Todos passam no cálculo do DV.
+        for d in '0'..='9' {
+            assert!(!cpf_valido(&d.to_string().repeat(11)), "{}", d);
+        }
+    }
+
+    #[test]
+    fn rejeita_cpf_com_tamanho_ou_caractere_invalido() {
+        assert!(!cpf_valido("5299822472"));
+        assert!(!cpf_valido("529982247250"));
+        assert!(!cpf_valido("5299822472A"));
+    }
+
+    #[test]
+    fn normaliza_documento_com_mascara() {
+        assert_eq!(normalizar_documento("11.222.333/0001-81".to_string()), "11222333000181");
+        assert_eq!(normalizar_documento("529.982.247-25".to_string()), "52998224725");
+        assert_eq!(normalizar_documento(" 12.abc.345/01de-35 ".to_string()), "12ABC34501DE35");
+        assert!(cnpj_valido(&normalizar_documento("12.ABC.345/01DE-35".to_string())));
+    }
+
+    #[test]
+    fn mantem_documento_sem_mascara() {
+        assert_eq!(normalizar_documento("52998224725".to_string()), "52998224725");
+        assert_eq!(normalizar_documento(String::new()), "");
+    }
+}
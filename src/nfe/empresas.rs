@@ -0,0 +1,125 @@
+use std::{collections::HashMap, error::Error, fmt::Debug, fs};
+
+use serde::Serialize;
+
+use crate::{
+    nfe::documento::normalizar_documento,
+    nfes::{EmitenteId, NFe},
+};
+
+/// Cadastro que informa os CNPJs/CPFs pertencentes a cada `company_id`.
+///
+/// Um documento de 8 caracteres é tratado como raiz de CNPJ e vale para todos os estabelecimentos.
+pub trait CadastroEmpresas: Debug + Send + Sync {
+    fn documentos(&self, company_id: i64) -> Vec<String>;
+}
+
+/// Cadastro carregado de um CSV local (`company_id;documento`), uma linha por documento.
+#[derive(Debug, Default, Clone)]
+pub struct CadastroArquivo {
+    empresas: HashMap<i64, Vec<String>>,
+}
+
+impl CadastroArquivo {
+    /// Aceita `;` ou `,` como separador. Uma primeira linha não numérica é considerada cabeçalho.
+    pub fn carregar(caminho: &str) -> Result<CadastroArquivo, Box<dyn Error>> {
+        let conteudo: String = fs::read_to_string(caminho)?;
+        let mut cadastro: CadastroArquivo = CadastroArquivo::default();
+
+        for (i, linha) in conteudo.lines().enumerate() {
+            let linha: &str = linha.trim();
+            if linha.is_empty() {
+                continue;
+            }
+            let separador: char = if linha.contains(';') { ';' } else { ',' };
+            let Some((company_id, documento)) = linha.split_once(separador) else {
+                return Err(format!("{}:{}: esperado company_id{}documento", caminho, i + 1, separador).into());
+            };
+            let company_id: i64 = match company_id.trim().parse() {
+                Ok(id) => id,
+                Err(_) if i == 0 => continue,
+                Err(e) => return Err(format!("{}:{}: company_id '{}' inválido: {}", caminho, i + 1, company_id, e).into()),
+            };
+            let documento: String = normalizar_documento(documento.trim().to_string());
+            if documento.is_empty() {
+                return Err(format!("{}:{}: documento vazio", caminho, i + 1).into());
+            }
+
+            cadastro.empresas.entry(company_id).or_default().push(documento);
+        }
+        Ok(cadastro)
+    }
+}
+
+impl CadastroEmpresas for CadastroArquivo {
+    fn documentos(&self, company_id: i64) -> Vec<String> {
+        self.empresas.get(&company_id).cloned().unwrap_or_default()
+    }
+}
+
+/// Papel da empresa solicitante no documento.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Participacao {
+    Emitida,
+    Recebida,
+    /// Presente apenas em `autXML`.
+    Terceiro,
+    SemVinculo,
+}
+
+/// Sentido da mercadoria para a empresa solicitante.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Movimento {
+    Entrada,
+    Saida,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Classificacao {
+    pub participacao: Participacao,
+    pub movimento: Option<Movimento>,
+}
+
+/// Classifica a NF-e em relação aos documentos da empresa.
+///
+/// O emitente prevalece sobre o destinatário (ex.: transferência entre filiais).
+/// O movimento segue o `tpNF` para a nota emitida e o inverte para a recebida;
+/// terceiros e notas sem vínculo não têm movimento.
+pub fn classificar(nfe: &NFe, documentos: &[String]) -> Classificacao {
+    let pertence = |id: &EmitenteId| documento_pertence(id, documentos);
+
+    let participacao: Participacao = if pertence(&nfe.emit.EmitenteId) {
+        Participacao::Emitida
+    } else if nfe.dest.as_ref().is_some_and(|d| pertence(&d.EmitenteId)) {
+        Participacao::Recebida
+    } else if nfe.autXML.iter().flatten().any(pertence) {
+        Participacao::Terceiro
+    } else {
+        Participacao::SemVinculo
+    };
+
+    let saida: bool = nfe.ide.tpNF;
+    let movimento: Option<Movimento> = match participacao {
+        Participacao::Emitida if saida => Some(Movimento::Saida),
+        Participacao::Emitida => Some(Movimento::Entrada),
+        Participacao::Recebida if saida => Some(Movimento::Entrada),
+        Participacao::Recebida => Some(Movimento::Saida),
+        Participacao::Terceiro | Participacao::SemVinculo => None,
+    };
+
+    Classificacao { participacao, movimento }
+}
+
+fn documento_pertence(id: &EmitenteId, documentos: &[String]) -> bool {
+    let documento: &str = match id {
+        EmitenteId::CNPJ(doc) | EmitenteId::CPF(doc) => doc,
+        EmitenteId::idEstrangeiro(_) => return false,
+    };
+    if documento.is_empty() {
+        return false;
+    }
+    documentos.iter().any(|d| match (d.len(), id) {
+        (8, EmitenteId::CNPJ(_)) => documento.starts_with(d.as_str()),
+        _ => documento == d,
+    })
+}
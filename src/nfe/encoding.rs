@@ -0,0 +1,145 @@
+use std::borrow::Cow;
+
+use bytes::Bytes;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+
+use crate::nfe::{common::ParseError, config::get_config, validacao::Diagnostico};
+
+/// Tamanho máximo lido do início do arquivo para encontrar a declaração `<?xml ... ?>`.
+const DECLARACAO_MAX: usize = 256;
+
+const ENCODING_DIVERGENTE: &str = "ENCODING_DIVERGENTE";
+
+/// XML já convertido para UTF-8, junto do encoding original do arquivo.
+pub struct XmlUtf8 {
+    pub xml: Bytes,
+    pub encoding: String,
+    /// Preenchido quando o conteúdo não corresponde ao encoding declarado.
+    pub diagnostico: Option<Diagnostico>,
+}
+
+/// Converte o XML para UTF-8, respeitando o BOM e o `encoding` da declaração XML.
+///
+/// Emissores antigos enviam `ISO-8859-1`/`Windows-1252`, e alguns declaram UTF-8 mas
+/// gravam Latin-1. Nesse último caso o conteúdo é decodificado como Windows-1252, que passa a
+/// ser o `encoding` informado, com um diagnóstico; no modo estrito o documento é rejeitado.
+pub fn decode_xml(xml: Bytes) -> Result<XmlUtf8, ParseError> {
+    decodificar(xml, get_config().strict)
+}
+
+fn decodificar(xml: Bytes, estrito: bool) -> Result<XmlUtf8, ParseError> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(&xml) {
+        log::debug!("BOM {} encontrado no XML", encoding.name());
+        let sem_bom: Bytes = xml.slice(bom_len..);
+        return decode_com(sem_bom, encoding, encoding.name().to_string(), estrito);
+    }
+
+    match get_encoding_declarado(&xml) {
+        Some(label) => match Encoding::for_label(label.as_bytes()) {
+            Some(encoding) => decode_com(xml, encoding, label.to_uppercase(), estrito),
+            None => Err(ParseError::Xml(format!("Encoding não suportado: {}", label))),
+        },
+        None => decode_com(xml, UTF_8, UTF_8.name().to_string(), estrito),
+    }
+}
+
+fn decode_com(xml: Bytes, encoding: &'static Encoding, nome: String, estrito: bool) -> Result<XmlUtf8, ParseError> {
+    if encoding == UTF_8 {
+        if std::str::from_utf8(&xml).is_ok() {
+            return Ok(XmlUtf8 { xml, encoding: nome, diagnostico: None });
+        }
+        if estrito {
+            return Err(ParseError::Xml(format!("Conteúdo inválido para o encoding {}", nome)));
+        }
+
+        log::warn!("XML declarado como {} contém bytes inválidos, decodificando como Windows-1252", nome);
+        let mut convertido: XmlUtf8 = decode_com(xml, WINDOWS_1252, WINDOWS_1252.name().to_string(), estrito)?;
+        convertido.diagnostico = Some(Diagnostico::new(
+            ENCODING_DIVERGENTE,
+            "encoding",
+            format!("Declarado {}, mas com bytes inválidos; decodificado como {}", nome, convertido.encoding),
+        ));
+        return Ok(convertido);
+    }
+
+    let txt: Cow<str> = encoding
+        .decode_without_bom_handling_and_without_replacement(&xml)
+        .ok_or_else(|| ParseError::Xml(format!("Conteúdo inválido para o encoding {}", nome)))?;
+
+    Ok(XmlUtf8 {
+        xml: Bytes::from(txt.into_owned()),
+        encoding: nome,
+        diagnostico: None,
+    })
+}
+
+/// Lê o pseudo-atributo `encoding` da declaração `<?xml version="1.0" encoding="..."?>`.
+fn get_encoding_declarado(xml: &[u8]) -> Option<String> {
+    let inicio: &[u8] = &xml[..xml.len().min(DECLARACAO_MAX)];
+    if !inicio.starts_with(b"<?xml") {
+        return None;
+    }
+
+    let fim: usize = inicio.windows(2).position(|w| w == b"?>")?;
+    let declaracao: &str = std::str::from_utf8(&inicio[..fim]).ok()?;
+
+    let resto: &str = &declaracao[declaracao.find("encoding")? + "encoding".len()..];
+    let resto: &str = resto.trim_start().strip_prefix('=')?.trim_start();
+
+    let aspas: char = resto.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let valor: &str = &resto[1..];
+    let label: &str = &valor[..valor.find(aspas)?];
+
+    Some(label.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPO: &str = "<nfeProc><xNome>Açúcar União</xNome></nfeProc>";
+
+    fn latin1(texto: &str) -> Vec<u8> {
+        texto.chars().map(|c| c as u32 as u8).collect()
+    }
+
+    #[test]
+    fn remove_o_bom_utf8() {
+        let mut xml: Vec<u8> = b"\xEF\xBB\xBF".to_vec();
+        xml.extend_from_slice(CORPO.as_bytes());
+        let r: XmlUtf8 = decodificar(Bytes::from(xml), true).unwrap();
+        assert_eq!(r.xml, CORPO.as_bytes());
+        assert_eq!(r.encoding, "UTF-8");
+        assert!(r.diagnostico.is_none());
+    }
+
+    #[test]
+    fn converte_iso_8859_1_declarado() {
+        let texto: String = format!("<?xml version=\"1.0\" encoding=\"iso-8859-1\"?>{}", CORPO);
+        let r: XmlUtf8 = decodificar(Bytes::from(latin1(&texto)), true).unwrap();
+        assert_eq!(r.xml, texto.as_bytes());
+        assert_eq!(r.encoding, "ISO-8859-1");
+        assert!(r.diagnostico.is_none());
+    }
+
+    #[test]
+    fn utf8_invalido_e_decodificado_como_windows_1252() {
+        let texto: String = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>{}", CORPO);
+        let r: XmlUtf8 = decodificar(Bytes::from(latin1(&texto)), false).unwrap();
+        assert_eq!(r.xml, texto.as_bytes());
+        assert_eq!(r.encoding, "windows-1252");
+        assert_eq!(r.diagnostico.map(|d| d.regra), Some(ENCODING_DIVERGENTE));
+    }
+
+    #[test]
+    fn utf8_invalido_e_rejeitado_no_modo_estrito() {
+        let texto: String = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>{}", CORPO);
+        assert!(decodificar(Bytes::from(latin1(&texto)), true).is_err());
+    }
+
+    #[test]
+    fn sem_declaracao_assume_utf8() {
+        let r: XmlUtf8 = decodificar(Bytes::from(CORPO), true).unwrap();
+        assert_eq!(r.encoding, "UTF-8");
+    }
+}
@@ -3,11 +3,11 @@
 use std::error::Error;
 
 use bytes::Bytes;
-use quick_xml::{Reader, events::Event};
+use quick_xml::events::Event;
 use serde::Serialize;
 
 use crate::{
-    nfe::common::{ParseError, XmlReader, get_tag_attribute, read_text},
+    nfe::{common::{ParseError, XmlReader, get_tag_attribute, read_text}, data_hora::{parse_data_hora, DataHora}, documento::normalizar_documento, validacao::Diagnostico},
     nfes::EmitenteId,
 };
 
@@ -15,7 +15,9 @@ use crate::{
 pub struct EventoJson {
     pub company_id: i64,
     pub org_id: i64,
+    pub encoding: String,
     pub eventos: Vec<Evento>,
+    pub diagnosticos: Vec<Diagnostico>,
 }
 
 #[derive(Debug, Serialize)]
@@ -52,7 +54,7 @@ pub struct InfEvento {
     pub CpfCnpj: EmitenteId,
 
     pub chNFe: String,
-    pub dhEvento: String,
+    pub dhEvento: DataHora,
     pub tpEvento: String,
     pub nSeqEvento: String,
     pub verEvento: String,
@@ -80,7 +82,7 @@ pub struct TRetEvento {
     pub xEvento: Option<String>,
     pub nSeqEvento: Option<String>,
     pub cOrgaoAutor: Option<String>,
-    pub dhRegEvento: String,
+    pub dhRegEvento: DataHora,
     pub nProt: Option<String>,
 }
 
@@ -91,34 +93,39 @@ pub struct TProcEvento {
 }
 
 pub fn parse_evento_nfe(xml: Bytes) -> Result<EventoJson, Box<dyn Error>> {
-    let mut evento_json: EventoJson = EventoJson::default();
+    let mut reader: XmlReader = XmlReader::from_reader(&xml);
+    let mut evento_json: EventoJson = parse_eventos(&mut reader).map_err(|e| reader.erro_no_caminho(e))?;
+    evento_json.diagnosticos = reader.coletar_diagnosticos();
+    Ok(evento_json)
+}
 
-    let mut reader: Reader<&[u8]> = Reader::from_reader(&xml);
+fn parse_eventos(reader: &mut XmlReader) -> Result<EventoJson, Box<dyn Error>> {
+    let mut evento_json: EventoJson = EventoJson::default();
 
     loop {
         match reader.read_event()? {
             Event::Start(e) => match e.name().as_ref() {
                 b"evento" => {
-                    let evento: TEvento = parse_evento(&mut reader)?;
+                    let evento: TEvento = parse_evento(reader)?;
                     evento_json.eventos.push(Evento::evento(evento));
                     log::debug!("Parse Evento OK");
                     return Ok(evento_json);
                 }
 
                 b"procEventoNFe" => {
-                    let evento: TProcEvento = parse_procEventoNFe(&mut reader)?;
+                    let evento: TProcEvento = parse_procEventoNFe(reader)?;
                     evento_json.eventos.push(Evento::procEventoNFe(evento));
                     return Ok(evento_json);
                 }
 
                 b"envEvento" => {
-                    let eventos: Vec<Evento> = parse_envEvento(&mut reader)?;
+                    let eventos: Vec<Evento> = parse_envEvento(reader)?;
                     evento_json.eventos = eventos;
                     return Ok(evento_json);   
                 }
 
                 b"retEnvEvento" => {
-                    let eventos: Vec<Evento> = parse_retEnvEvento(&mut reader)?;
+                    let eventos: Vec<Evento> = parse_retEnvEvento(reader)?;
                     evento_json.eventos = eventos;
                     return Ok(evento_json);
                 }
@@ -160,10 +167,10 @@ fn parse_evento(reader: &mut XmlReader) -> Result<TEvento, Box<dyn Error>> {
                         match name {
                             b"cOrgao" => infEvento.cOrgao = txt,
                             b"tpAmb" => infEvento.tpAmb = txt,
-                            b"CNPJ" => infEvento.CpfCnpj = EmitenteId::CNPJ(txt),
-                            b"CPF" => infEvento.CpfCnpj = EmitenteId::CPF(txt),
+                            b"CNPJ" => infEvento.CpfCnpj = EmitenteId::CNPJ(normalizar_documento(txt)),
+                            b"CPF" => infEvento.CpfCnpj = EmitenteId::CPF(normalizar_documento(txt)),
                             b"chNFe" => infEvento.chNFe = txt,
-                            b"dhEvento" => infEvento.dhEvento = txt,
+                            b"dhEvento" => infEvento.dhEvento = parse_data_hora(txt, "infEvento.dhEvento")?,
                             b"tpEvento" => infEvento.tpEvento = txt,
                             b"nSeqEvento" => infEvento.nSeqEvento = txt,
                             b"verEvento" => infEvento.verEvento = txt,
@@ -235,7 +242,7 @@ fn parse_retEvento(reader: &mut XmlReader) -> Result<TRetEvento, Box<dyn Error>>
                             b"xEvento" => ret.xEvento = Some(txt),
                             b"nSeqEvento" => ret.nSeqEvento = Some(txt),
                             b"cOrgaoAutor" => ret.cOrgaoAutor = Some(txt),
-                            b"dhRegEvento" => ret.dhRegEvento = txt,
+                            b"dhRegEvento" => ret.dhRegEvento = parse_data_hora(txt, "retEvento.dhRegEvento")?,
                             b"nProt" => ret.nProt = Some(txt),
                             _ => (),
                         }
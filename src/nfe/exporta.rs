@@ -4,11 +4,11 @@ use std::error::Error;
 
 use quick_xml::events::Event;
 use serde::Serialize;
-use crate::{nfe::common::{read_text, ParseError, XmlReader}, nfes::UF};
+use crate::{nfe::common::{read_text, ParseError, XmlReader}, nfes::{parse_uf, UF}};
 
 #[derive(Debug, Default, Serialize)]
 pub struct Exporta {
-    pub UFSaidaPais: UF,
+    pub UFSaidaPais: Option<UF>,
 
     pub xLocExporta: String,
 
@@ -23,7 +23,7 @@ pub fn parse_exporta(reader: &mut XmlReader) -> Result<Exporta, Box<dyn Error>>
             Event::Start(e) => {
                 let txt = read_text(reader, &e)?;
                 match e.name().as_ref() {
-                    b"UFSaidaPais" => exporta.UFSaidaPais = UF::from(txt.as_str()),
+                    b"UFSaidaPais" => exporta.UFSaidaPais = parse_uf(reader, txt, "exporta.UFSaidaPais")?,
                     b"xLocExporta" => exporta.xLocExporta = txt,
                     b"xLocDespacho" => exporta.xLocDespacho = Some(txt),
                     _ => (),
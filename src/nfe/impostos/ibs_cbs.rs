@@ -10,6 +10,8 @@ use crate::nfe::impostos::{cibs::TCIBS, monofasia::TMonofasia};
 pub struct IBSCBS {
     pub CST: String,
     pub cClassTrib: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cClassTribDesc: Option<&'static str>,
     #[serde(flatten)]
     pub tributacao: Option<TributacaoIBS>,
     pub gCredPresIBSZFM: Option<TCredPresIBSZFM>
@@ -2,7 +2,7 @@
 use rust_decimal::Decimal;
 use serde::Serialize;
 
-use crate::nfes::UF;
+use crate::{nfe::codigos::{ModBC, ModBCST, MotDesICMS}, nfes::UF};
 
 
 #[derive(Debug, Default, Serialize)]
@@ -19,7 +19,9 @@ pub struct Icms {
 
     // --- CÁLCULO ICMS NORMAL (CST 00, 10, 20, 51, 70, 90, Part) ---
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub modBC: Option<String>,
+    pub modBC: Option<ModBC>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modBCDesc: Option<&'static str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vBC: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -41,7 +43,9 @@ pub struct Icms {
 
     // --- ICMS ST (CÁLCULO NA OPERAÇÃO) ---
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub modBCST: Option<String>,
+    pub modBCST: Option<ModBCST>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modBCSTDesc: Option<&'static str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pMVAST: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -83,7 +87,9 @@ pub struct Icms {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vICMSDeson: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub motDesICMS: Option<String>,
+    pub motDesICMS: Option<MotDesICMS>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub motDesICMSDesc: Option<&'static str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub indDeduzDeson: Option<bool>,
     
@@ -91,7 +97,9 @@ pub struct Icms {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vICMSSTDeson: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub motDesICMSST: Option<String>,
+    pub motDesICMSST: Option<MotDesICMS>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub motDesICMSSTDesc: Option<&'static str>,
 
     // --- ICMS EFETIVO ---
     #[serde(skip_serializing_if = "Option::is_none")]
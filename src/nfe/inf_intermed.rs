@@ -4,7 +4,7 @@ use std::error::Error;
 use quick_xml::events::Event;
 use serde::Serialize;
 
-use crate::nfe::common::{read_text, ParseError, XmlReader};
+use crate::nfe::{common::{read_text, ParseError, XmlReader}, documento::normalizar_documento};
 
 
 
@@ -21,7 +21,7 @@ pub fn parse_infIntermed(reader: &mut XmlReader) -> Result<InfIntermed, Box<dyn
             Event::Start(e) => {
                 let txt: String = read_text(reader, &e)?;
                 match e.name().as_ref() {
-                    b"CNPJ" => inf_intermed.CNPJ = txt,
+                    b"CNPJ" => inf_intermed.CNPJ = normalizar_documento(txt),
                     b"idCadIntTran" => inf_intermed.idCadIntTran = txt,
                     _ => (),
                 }
@@ -1,10 +1,10 @@
 #![allow(non_snake_case, non_camel_case_types)]
 use std::error::Error;
 
-use quick_xml::{events::Event, Reader};
+use quick_xml::events::Event;
 use serde::{Deserialize, Serialize};
 
-use crate::nfe::common::{read_text, ParseError};
+use crate::nfe::{common::{read_text, ParseError, XmlReader}, documento::normalizar_documento};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct TInfRespTec {
@@ -21,7 +21,7 @@ pub struct TInfRespTec {
 }
 
 
-pub fn parse_infRespTec(reader: &mut Reader<&[u8]>) -> Result<TInfRespTec, Box<dyn Error>> {
+pub fn parse_infRespTec(reader: &mut XmlReader) -> Result<TInfRespTec, Box<dyn Error>> {
     let mut resp: TInfRespTec = TInfRespTec::default();
 
     loop {
@@ -30,7 +30,7 @@ pub fn parse_infRespTec(reader: &mut Reader<&[u8]>) -> Result<TInfRespTec, Box<d
                 let txt = read_text(reader, &e)?;
                 match e.name().as_ref() {
                     
-                    b"CNPJ" => resp.CNPJ = txt,
+                    b"CNPJ" => resp.CNPJ = normalizar_documento(txt),
                     b"xContato" => resp.xContato = txt,
                     b"email" => resp.email = txt,
                     b"fone" => resp.fone = txt,
@@ -0,0 +1,330 @@
+use crate::nfes::UF;
+
+/// Valida a Inscrição Estadual conforme o algoritmo de dígito verificador da UF (roteiros do SINTEGRA).
+///
+/// Máscaras e espaços são ignorados. O literal `ISENTO` é sempre aceito.
+pub fn ie_valida(uf: UF, ie: &str) -> bool {
+    let ie: String = ie
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    if ie == "ISENTO" {
+        return true;
+    }
+
+    // Produtor rural de SP: P0MMMSSSSD000
+    if let Some(rural) = ie.strip_prefix('P') {
+        return uf == UF::SP && get_digitos(rural).is_some_and(|d| ie_sp_rural(&d));
+    }
+
+    let Some(d) = get_digitos(&ie) else {
+        return false;
+    };
+    // Antes do preenchimento com zeros (MT): vazia ou só zeros passaria no cálculo do DV.
+    if d.iter().all(|&x| x == 0) {
+        return false;
+    }
+
+    match uf {
+        UF::AC => d.len() == 13 && prefixo(&d, &[0, 1]) && dv_ac_df(&d),
+        UF::AL => ie_al(&d),
+        UF::AM => ie_am(&d),
+        UF::AP => ie_ap(&d),
+        UF::BA => ie_ba(&d),
+        UF::CE | UF::ES | UF::PB | UF::PI | UF::SC | UF::SE => d.len() == 9 && confere(&d, 8, &pesos(8, 9), mod11),
+        UF::DF => d.len() == 13 && prefixo(&d, &[0, 7]) && dv_ac_df(&d),
+        UF::GO => ie_go(&d),
+        UF::MA => d.len() == 9 && prefixo(&d, &[1, 2]) && confere(&d, 8, &pesos(8, 9), mod11),
+        UF::MG => ie_mg(&d),
+        UF::MS => d.len() == 9 && (prefixo(&d, &[2, 8]) || prefixo(&d, &[5, 0])) && confere(&d, 8, &pesos(8, 9), mod11),
+        UF::MT => ie_mt(&d),
+        UF::PA => d.len() == 9 && prefixo(&d, &[1, 5]) && confere(&d, 8, &pesos(8, 9), mod11),
+        UF::PE => ie_pe(&d),
+        UF::PR => d.len() == 10 && confere(&d, 8, &pesos(8, 7), mod11) && confere(&d, 9, &pesos(9, 7), mod11),
+        UF::RJ => d.len() == 8 && confere(&d, 7, &pesos(7, 7), mod11),
+        UF::RN => ie_rn(&d),
+        UF::RO => ie_ro(&d),
+        UF::RR => ie_rr(&d),
+        UF::RS => d.len() == 10 && confere(&d, 9, &pesos(9, 9), mod11),
+        UF::SP => ie_sp(&d),
+        UF::TO => ie_to(&d),
+        UF::EX => true,
+    }
+}
+
+fn get_digitos(txt: &str) -> Option<Vec<u32>> {
+    txt.chars().map(|c| c.to_digit(10)).collect()
+}
+
+fn prefixo(d: &[u32], p: &[u32]) -> bool {
+    d.starts_with(p)
+}
+
+/// Pesos de 2 até `max`, da direita para a esquerda, reiniciando em 2 ao passar de `max`.
+fn pesos(n: usize, max: u32) -> Vec<u32> {
+    let mut pesos: Vec<u32> = (0..n as u32).map(|i| 2 + i % (max - 1)).collect();
+    pesos.reverse();
+    pesos
+}
+
+fn soma(d: &[u32], pesos: &[u32]) -> u32 {
+    d.iter().zip(pesos).map(|(d, p)| d * p).sum()
+}
+
+/// Confere o dígito na posição `pos` calculado sobre as posições anteriores.
+fn confere(d: &[u32], pos: usize, pesos: &[u32], calculo: fn(u32) -> u32) -> bool {
+    calculo(soma(&d[..pos], pesos)) == d[pos]
+}
+
+/// Módulo 11 com resto 0 ou 1 resultando em 0.
+fn mod11(soma: u32) -> u32 {
+    match soma % 11 {
+        0 | 1 => 0,
+        resto => 11 - resto,
+    }
+}
+
+fn mod10(soma: u32) -> u32 {
+    (10 - soma % 10) % 10
+}
+
+/// Módulo 11 que subtrai 10 quando o resultado passa de 9 (RO e o formato antigo de PE).
+fn mod11_menos_dez(soma: u32) -> u32 {
+    let dv: u32 = 11 - soma % 11;
+    if dv >= 10 { dv - 10 } else { dv }
+}
+
+/// (soma * 10) mod 11, com 10 resultando em 0 (AL e RN).
+fn mod11_vezes_dez(soma: u32) -> u32 {
+    (soma * 10) % 11 % 10
+}
+
+fn dv_ac_df(d: &[u32]) -> bool {
+    confere(d, 11, &pesos(11, 9), mod11) && confere(d, 12, &pesos(12, 9), mod11)
+}
+
+fn ie_al(d: &[u32]) -> bool {
+    d.len() == 9 && prefixo(d, &[2, 4]) && [0, 3, 5, 7, 8].contains(&d[2]) && confere(d, 8, &pesos(8, 9), mod11_vezes_dez)
+}
+
+fn ie_am(d: &[u32]) -> bool {
+    if d.len() != 9 {
+        return false;
+    }
+    let s: u32 = soma(&d[..8], &pesos(8, 9));
+    let dv: u32 = if s < 11 { 11 - s } else { mod11(s) };
+    dv == d[8]
+}
+
+fn ie_ap(d: &[u32]) -> bool {
+    if d.len() != 9 || !prefixo(d, &[0, 3]) {
+        return false;
+    }
+    let numero: u32 = d[..8].iter().fold(0, |acc, x| acc * 10 + x);
+    let (p, dv_padrao) = match numero {
+        3000001..=3017000 => (5, 0),
+        3017001..=3019022 => (9, 1),
+        _ => (0, 0),
+    };
+    let dv: u32 = match 11 - (p + soma(&d[..8], &pesos(8, 9))) % 11 {
+        10 => 0,
+        11 => dv_padrao,
+        dv => dv,
+    };
+    dv == d[8]
+}
+
+/// 8 ou 9 dígitos. O segundo dígito verificador é calculado primeiro e entra no cálculo do primeiro.
+/// O módulo (10 ou 11) depende do primeiro dígito (8 posições) ou do segundo (9 posições).
+fn ie_ba(d: &[u32]) -> bool {
+    let base: usize = match d.len() {
+        8 => 6,
+        9 => 7,
+        _ => return false,
+    };
+    let calculo: fn(u32) -> u32 = match d[d.len() - 8] {
+        6 | 7 | 9 => mod11,
+        _ => mod10,
+    };
+
+    let dv2: u32 = calculo(soma(&d[..base], &pesos(base, base as u32 + 1)));
+    let mut com_dv2: Vec<u32> = d[..base].to_vec();
+    com_dv2.push(dv2);
+    let dv1: u32 = calculo(soma(&com_dv2, &pesos(base + 1, base as u32 + 2)));
+
+    dv1 == d[base] && dv2 == d[base + 1]
+}
+
+fn ie_go(d: &[u32]) -> bool {
+    if d.len() != 9 || !(prefixo(d, &[1, 0]) || prefixo(d, &[1, 1]) || prefixo(d, &[1, 5]) || d[0] == 2) {
+        return false;
+    }
+    let numero: u32 = d[..8].iter().fold(0, |acc, x| acc * 10 + x);
+    let dv: u32 = match soma(&d[..8], &pesos(8, 9)) % 11 {
+        0 => 0,
+        1 if (10103105..=10119997).contains(&numero) => 1,
+        1 => 0,
+        resto => 11 - resto,
+    };
+    dv == d[8]
+}
+
+/// 13 dígitos. O primeiro DV soma os algarismos dos produtos (pesos 1 e 2 alternados), com um
+/// zero inserido após o código do município.
+fn ie_mg(d: &[u32]) -> bool {
+    if d.len() != 13 {
+        return false;
+    }
+    let mut base: Vec<u32> = d[..3].to_vec();
+    base.push(0);
+    base.extend_from_slice(&d[3..11]);
+
+    let s: u32 = base
+        .iter()
+        .enumerate()
+        .map(|(i, x)| x * (1 + i as u32 % 2))
+        .map(|p| p / 10 + p % 10)
+        .sum();
+
+    mod10(s) == d[11] && confere(d, 12, &pesos(12, 11), mod11)
+}
+
+/// 11 dígitos, completando com zeros à esquerda.
+fn ie_mt(d: &[u32]) -> bool {
+    if d.len() > 11 {
+        return false;
+    }
+    let mut completo: Vec<u32> = vec![0; 11 - d.len()];
+    completo.extend_from_slice(d);
+    confere(&completo, 10, &pesos(10, 9), mod11)
+}
+
+/// eFisco (9 dígitos, dois DVs) ou o antigo CACEPE (14 dígitos).
+fn ie_pe(d: &[u32]) -> bool {
+    match d.len() {
+        9 => confere(d, 7, &pesos(7, 8), mod11) && confere(d, 8, &pesos(8, 9), mod11),
+        14 => confere(d, 13, &[5, 4, 3, 2, 1, 9, 8, 7, 6, 5, 4, 3, 2], mod11_menos_dez),
+        _ => false,
+    }
+}
+
+fn ie_rn(d: &[u32]) -> bool {
+    matches!(d.len(), 9 | 10) && prefixo(d, &[2, 0]) && confere(d, d.len() - 1, &pesos(d.len() - 1, d.len() as u32), mod11_vezes_dez)
+}
+
+/// 14 dígitos ou o formato antigo de 9, em que os 3 primeiros (município) ficam fora do cálculo.
+fn ie_ro(d: &[u32]) -> bool {
+    match d.len() {
+        14 => confere(d, 13, &pesos(13, 9), mod11_menos_dez),
+        9 => confere(&d[3..], 5, &pesos(5, 6), mod11_menos_dez),
+        _ => false,
+    }
+}
+
+fn ie_rr(d: &[u32]) -> bool {
+    d.len() == 9 && prefixo(d, &[2, 4]) && soma(&d[..8], &[1, 2, 3, 4, 5, 6, 7, 8]) % 9 == d[8]
+}
+
+/// 12 dígitos, com DVs na 9ª e na 12ª posições.
+fn ie_sp(d: &[u32]) -> bool {
+    let sp = |s: u32| s % 11 % 10;
+    d.len() == 12 && confere(d, 8, &[1, 3, 4, 5, 6, 7, 8, 10], sp) && confere(d, 11, &pesos(11, 10), sp)
+}
+
+fn ie_sp_rural(d: &[u32]) -> bool {
+    d.len() == 12 && confere(d, 8, &[1, 3, 4, 5, 6, 7, 8, 10], |s| s % 11 % 10)
+}
+
+/// 9 dígitos, ou o formato antigo de 11 em que a 3ª e a 4ª posições (tipo de empresa) ficam fora do cálculo.
+fn ie_to(d: &[u32]) -> bool {
+    match d.len() {
+        9 => confere(d, 8, &pesos(8, 9), mod11),
+        11 if matches!(d[2] * 10 + d[3], 1 | 2 | 3 | 99) => {
+            let mut base: Vec<u32> = d[..2].to_vec();
+            base.extend_from_slice(&d[4..]);
+            confere(&base, 8, &pesos(8, 9), mod11)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exemplos dos roteiros de cálculo do SINTEGRA.
+    const EXEMPLOS: [(UF, &str); 31] = [
+        (UF::AC, "01.004.823/001-12"),
+        (UF::AL, "240000048"),
+        (UF::AP, "030123459"),
+        (UF::AM, "99.999.999-0"),
+        (UF::BA, "123456-63"),
+        (UF::BA, "1000003-06"),
+        (UF::CE, "06000001-5"),
+        (UF::DF, "07300001001-09"),
+        (UF::ES, "999999990"),
+        (UF::GO, "10.987.654-7"),
+        (UF::MA, "12000038-5"),
+        (UF::MT, "0013000001-9"),
+        // Calculada pelo roteiro de MS (módulo 11, pesos 9 a 2).
+        (UF::MS, "28.312.345-1"),
+        (UF::MG, "062.307.904/0081"),
+        (UF::PA, "15-999999-5"),
+        (UF::PB, "06000001-5"),
+        (UF::PR, "123.45678-50"),
+        (UF::PE, "0321418-40"),
+        (UF::PE, "18.1.001.0000004-9"),
+        (UF::PI, "012345679"),
+        (UF::RJ, "99.999.99-3"),
+        (UF::RN, "20.040.040-1"),
+        (UF::RN, "20.0.040.040-0"),
+        (UF::RS, "224/3658792"),
+        (UF::RO, "0000000062521-3"),
+        (UF::RO, "101.62521-3"),
+        (UF::RR, "24006628-1"),
+        (UF::SC, "251.040.852"),
+        (UF::SP, "110.042.490.114"),
+        (UF::SE, "27123456-3"),
+        (UF::TO, "29.01.022783-6"),
+    ];
+
+    #[test]
+    fn aceita_exemplos_do_sintegra() {
+        let falhas: Vec<_> = EXEMPLOS.iter().filter(|(uf, ie)| !ie_valida(*uf, ie)).collect();
+        assert!(falhas.is_empty(), "{:?}", falhas);
+    }
+
+    #[test]
+    fn rejeita_exemplos_com_dv_alterado() {
+        for (uf, ie) in EXEMPLOS {
+            let ultimo: char = ie.chars().last().unwrap();
+            let trocado: char = char::from_digit((ultimo.to_digit(10).unwrap() + 1) % 10, 10).unwrap();
+            let alterado: String = format!("{}{}", &ie[..ie.len() - 1], trocado);
+            assert!(!ie_valida(uf, &alterado), "{:?} {}", uf, alterado);
+        }
+    }
+
+    #[test]
+    fn aceita_produtor_rural_de_sp() {
+        assert!(ie_valida(UF::SP, "P-01100424.3/002"));
+        assert!(!ie_valida(UF::MG, "P-01100424.3/002"));
+    }
+
+    #[test]
+    fn aceita_isento() {
+        assert!(ie_valida(UF::SP, "ISENTO"));
+        assert!(ie_valida(UF::MT, "isento"));
+    }
+
+    #[test]
+    fn rejeita_vazia_ou_zerada() {
+        for uf in [UF::MT, UF::SP, UF::RO, UF::EX] {
+            assert!(!ie_valida(uf, ""), "{:?}", uf);
+            assert!(!ie_valida(uf, "  "), "{:?}", uf);
+            assert!(!ie_valida(uf, "0"), "{:?}", uf);
+            assert!(!ie_valida(uf, "00000000000"), "{:?}", uf);
+        }
+    }
+}
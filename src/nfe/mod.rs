@@ -2,7 +2,17 @@ pub mod det;
 pub mod impostos;
 pub mod eventos;
 
+pub mod chave;
+pub mod ciclo_vida;
+pub mod calculo;
+pub mod codigos;
 pub mod common;
+pub mod config;
+pub mod data_hora;
+pub mod documento;
+pub mod empresas;
+pub mod inscricao_estadual;
+pub mod encoding;
 pub mod total;
 pub mod transp;
 pub mod cobr;
@@ -13,4 +23,7 @@ pub mod exporta;
 pub mod compra;
 pub mod cana;
 pub mod inf_resp_tec;
-pub mod agropecuario;
\ No newline at end of file
+pub mod agropecuario;
+pub mod prot_nfe;
+pub mod tabelas;
+pub mod validacao;
\ No newline at end of file
@@ -6,7 +6,7 @@ use quick_xml::events::Event;
 use rust_decimal::Decimal;
 use serde::Serialize;
 
-use crate::{nfe::common::{read_text, ParseError, XmlReader}, nfes::UF};
+use crate::{nfe::{codigos::{descricao, parse_codigo, TPag}, common::{read_text, ParseError, XmlReader}, data_hora::{parse_data, Data}, documento::normalizar_documento}, nfes::{parse_uf, UF}};
 
 
 
@@ -19,10 +19,12 @@ pub struct Pag {
 #[derive(Debug, Default, Serialize)]
 pub struct DetPag {
     pub indPag: Option<String>,
-    pub tPag: String,
+    pub tPag: TPag,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tPagDesc: Option<&'static str>,
     pub xPag: Option<String>,
     pub vPag: Decimal,
-    pub dPag: Option<String>,
+    pub dPag: Option<Data>,
     pub CNPJPag: Option<String>,
     pub UFPag: Option<UF>,
     pub card: Option<Card>,
@@ -74,12 +76,15 @@ fn parse_detPag(reader: &mut XmlReader) -> Result<DetPag, Box<dyn Error>> {
                     let txt = read_text(reader, &e)?;
                     match name {
                         b"indPag" => det_pag.indPag = Some(txt),
-                        b"tPag" => det_pag.tPag = txt,
+                        b"tPag" => {
+                            det_pag.tPag = parse_codigo(txt, "pag.detPag.tPag")?;
+                            det_pag.tPagDesc = descricao(&det_pag.tPag);
+                        }
                         b"xPag" => det_pag.xPag = Some(txt),
                         b"vPag" => det_pag.vPag = txt.parse::<Decimal>()?,
-                        b"dPag" => det_pag.dPag = Some(txt),
-                        b"CNPJPag" => det_pag.CNPJPag = Some(txt),
-                        b"UFPag" => det_pag.UFPag = Some(UF::from(txt.as_str())),
+                        b"dPag" => det_pag.dPag = Some(parse_data(txt, "pag.detPag.dPag")?),
+                        b"CNPJPag" => det_pag.CNPJPag = Some(normalizar_documento(txt)),
+                        b"UFPag" => det_pag.UFPag = parse_uf(reader, txt, "pag.detPag.UFPag")?,
                         _ => (),
                     }
                 }
@@ -99,10 +104,10 @@ fn parse_card(reader: &mut XmlReader) -> Result<Card, Box<dyn Error>> {
                 let txt = read_text(reader, &e)?;
                 match e.name().as_ref() {
                     b"tpIntegra" => card.tpIntegra = txt,
-                    b"CNPJ" => card.CNPJ = Some(txt),
+                    b"CNPJ" => card.CNPJ = Some(normalizar_documento(txt)),
                     b"tBand" => card.tBand = Some(txt),
                     b"cAut" => card.cAut = Some(txt),
-                    b"CNPJReceb" => card.CNPJReceb = Some(txt),
+                    b"CNPJReceb" => card.CNPJReceb = Some(normalizar_documento(txt)),
                     b"idTermPag" => card.idTermPag = Some(txt),
                     _ => (),
                 }
@@ -0,0 +1,49 @@
+#![allow(non_snake_case, non_camel_case_types)]
+use std::error::Error;
+
+use quick_xml::events::Event;
+use serde::Serialize;
+
+use crate::nfe::{common::{get_tag_attribute, read_text, ParseError, XmlReader}, data_hora::{parse_data_hora, DataHora}};
+
+/// Protocolo de autorização da NF-e (<protNFe>), presente no <nfeProc>.
+#[derive(Debug, Default, Serialize)]
+pub struct ProtNFe {
+    pub Id: Option<String>,
+    pub tpAmb: String,
+    pub verAplic: String,
+    pub chNFe: String,
+    pub dhRecbto: DataHora,
+    pub nProt: Option<String>,
+    pub digVal: Option<String>,
+    pub cStat: String,
+    pub xMotivo: String,
+}
+
+pub fn parse_protNFe(reader: &mut XmlReader) -> Result<ProtNFe, Box<dyn Error>> {
+    let mut prot: ProtNFe = ProtNFe::default();
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"infProt" => prot.Id = get_tag_attribute(&e, b"Id").ok(),
+                name => {
+                    let txt: String = read_text(reader, &e)?;
+                    match name {
+                        b"tpAmb" => prot.tpAmb = txt,
+                        b"verAplic" => prot.verAplic = txt,
+                        b"chNFe" => prot.chNFe = txt,
+                        b"dhRecbto" => prot.dhRecbto = parse_data_hora(txt, "protNFe.dhRecbto")?,
+                        b"nProt" => prot.nProt = Some(txt),
+                        b"digVal" => prot.digVal = Some(txt),
+                        b"cStat" => prot.cStat = txt,
+                        b"xMotivo" => prot.xMotivo = txt,
+                        _ => (),
+                    }
+                }
+            },
+            Event::End(e) if e.name().as_ref() == b"protNFe" => return Ok(prot),
+            Event::Eof => return Err(Box::new(ParseError::UnexpectedEof("protNFe".to_string()))),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+pub mod mva;
+
+use std::{collections::HashMap, fs, io::ErrorKind, path::Path, sync::OnceLock};
+
+use crate::nfe::config::get_config;
+
+/// Tabela de referência. As embutidas no binário ficam em `tabelas/dados/*.csv`; a de CFOP
+/// é completa e as demais são amostras parciais, substituíveis pelas oficiais em `PARSER_TABELAS_DIR`.
+///
+/// O cabeçalho do arquivo traz a versão (`# versao:`), a vigência (`# vigencia:`) e se a tabela
+/// é completa (`# completa: sim`). Códigos ausentes só são reportados em tabelas completas.
+/// Colunas após a descrição ficam disponíveis em `colunas`.
+#[derive(Debug, Default)]
+pub struct Tabela {
+    pub nome: &'static str,
+    pub versao: String,
+    pub vigencia: String,
+    pub completa: bool,
+    itens: HashMap<String, Vec<String>>,
+}
+
+impl Tabela {
+    fn carregar(nome: &'static str, conteudo: &str) -> Tabela {
+        let mut tabela: Tabela = Tabela { nome, ..Default::default() };
+
+        for linha in conteudo.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some(meta) = linha.strip_prefix('#') {
+                match meta.split_once(':').map(|(k, v)| (k.trim(), v.trim())) {
+                    Some(("versao", v)) => tabela.versao = v.to_string(),
+                    Some(("vigencia", v)) => tabela.vigencia = v.to_string(),
+                    Some(("completa", v)) => tabela.completa = v == "sim",
+                    _ => (),
+                }
+                continue;
+            }
+            let mut colunas = linha.split(';').map(str::trim);
+            if let Some(codigo) = colunas.next()
+                && codigo != "codigo"
+            {
+                tabela.itens.insert(codigo.to_string(), colunas.map(str::to_string).collect());
+            }
+        }
+        tabela
+    }
+
+    pub fn buscar(&self, codigo: &str) -> Option<&str> {
+        self.colunas(codigo).and_then(|c| c.first()).map(String::as_str)
+    }
+
+    /// Colunas do código, a partir da descrição.
+    pub fn colunas(&self, codigo: &str) -> Option<&[String]> {
+        self.itens.get(codigo.trim()).map(Vec::as_slice)
+    }
+
+    /// `Some(false)` apenas quando a tabela é completa e o código não existe.
+    pub fn contem(&self, codigo: &str) -> Option<bool> {
+        match self.buscar(codigo) {
+            Some(_) => Some(true),
+            None if self.completa => Some(false),
+            None => None,
+        }
+    }
+}
+
+/// Declara as tabelas: um acessor por tabela, que usa a embutida enquanto `inicializar`
+/// não tiver carregado a de `PARSER_TABELAS_DIR`, e a lista percorrida por `inicializar`.
+macro_rules! tabelas {
+    ($($nome:ident: $celula:ident = $arquivo:literal),* $(,)?) => {
+        $(
+            static $celula: OnceLock<Tabela> = OnceLock::new();
+
+            pub fn $nome() -> &'static Tabela {
+                $celula.get_or_init(|| Tabela::carregar(stringify!($nome), include_str!(concat!("dados/", $arquivo))))
+            }
+        )*
+
+        /// (nome, tabela, arquivo, conteúdo embutido)
+        static FONTES: &[(&str, &OnceLock<Tabela>, &str, &str)] =
+            &[$((stringify!($nome), &$celula, $arquivo, include_str!(concat!("dados/", $arquivo)))),*];
+    };
+}
+
+tabelas! {
+    cfop: CFOP = "cfop.csv",
+    ncm: NCM = "ncm.csv",
+    cest: CEST = "cest.csv",
+    cclasstrib: CCLASSTRIB = "cclasstrib.csv",
+    municipios: MUNICIPIOS = "municipios.csv",
+}
+
+/// Conteúdo do arquivo de mesmo nome em `PARSER_TABELAS_DIR`, se houver.
+fn get_conteudo(arquivo: &str) -> Result<Option<String>, String> {
+    let Some(diretorio) = &get_config().tabelas_dir else {
+        return Ok(None);
+    };
+    let caminho = Path::new(diretorio).join(arquivo);
+    match fs::read_to_string(&caminho) {
+        Ok(conteudo) => Ok(Some(conteudo)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Tabela de referência {} ilegível: {}", caminho.display(), e)),
+    }
+}
+
+/// Carrega as tabelas na inicialização, preferindo as de `PARSER_TABELAS_DIR` às embutidas,
+/// e avisa quais não validam a existência dos códigos.
+pub fn inicializar() -> Result<(), String> {
+    for &(nome, celula, arquivo, embutido) in FONTES {
+        let tabela: Tabela = match get_conteudo(arquivo)? {
+            Some(conteudo) => Tabela::carregar(nome, &conteudo),
+            None => Tabela::carregar(nome, embutido),
+        };
+        let tabela: &Tabela = celula.get_or_init(|| tabela);
+        match tabela.completa {
+            true => log::info!(
+                "Tabela de referência {} versão {} (vigência {}) carregada com {} códigos",
+                tabela.nome, tabela.versao, tabela.vigencia, tabela.itens.len()
+            ),
+            false => log::warn!(
+                "Tabela de referência {} versão {} (vigência {}) é parcial, com {} códigos: códigos ausentes não são reportados",
+                tabela.nome, tabela.versao, tabela.vigencia, tabela.itens.len()
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Descrição do código na tabela, apenas quando `PARSER_DESCRICOES` estiver habilitado.
+pub fn descricao(tabela: fn() -> &'static Tabela, codigo: &str) -> Option<&'static str> {
+    if get_config().descricoes {
+        tabela().buscar(codigo)
+    } else {
+        None
+    }
+}
+
+/// Municípios cujo código IBGE não confere com o dígito verificador, mas são válidos.
+const MUNICIPIOS_DV_EXCECAO: [u32; 9] = [2201919, 2202251, 2201988, 2611533, 3117836, 3152131, 4305871, 5203939, 5203962];
+
+/// Código IBGE de município: 7 dígitos, o último verificador (pesos 1 e 2 alternados,
+/// somando os algarismos dos produtos).
+#[allow(non_snake_case)]
+pub fn municipio_valido(cMun: u32) -> bool {
+    if !(1000000..=9999999).contains(&cMun) {
+        return false;
+    }
+    if MUNICIPIOS_DV_EXCECAO.contains(&cMun) {
+        return true;
+    }
+    let digitos: Vec<u32> = cMun.to_string().chars().filter_map(|c| c.to_digit(10)).collect();
+    let soma: u32 = digitos[..6]
+        .iter()
+        .enumerate()
+        .map(|(i, d)| d * (1 + i as u32 % 2))
+        .map(|p| p / 10 + p % 10)
+        .sum();
+    (10 - soma % 10) % 10 == digitos[6]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn le_metadados_e_colunas() {
+        let tabela: Tabela = Tabela::carregar("teste", "# versao: 2025-01\n# vigencia: 2019-01-01\n# completa: sim\ncodigo;descricao;ncm\n0300100;Água mineral;22011000 22019000\n");
+        assert_eq!(tabela.versao, "2025-01");
+        assert_eq!(tabela.vigencia, "2019-01-01");
+        assert!(tabela.completa);
+        assert_eq!(tabela.buscar("0300100"), Some("Água mineral"));
+        assert_eq!(tabela.colunas("0300100").map(|c| c[1].as_str()), Some("22011000 22019000"));
+        assert_eq!(tabela.contem("0300200"), Some(false));
+    }
+
+    #[test]
+    fn tabela_parcial_nao_reporta_ausentes() {
+        let tabela: Tabela = Tabela::carregar("teste", "# completa: nao\ncodigo;descricao\n22030000;Cervejas de malte\n");
+        assert_eq!(tabela.contem("22030000"), Some(true));
+        assert_eq!(tabela.contem("22021000"), None);
+    }
+
+    #[test]
+    fn cfop_embutida_e_completa() {
+        let tabela: &Tabela = cfop();
+        assert!(tabela.completa);
+        for codigo in ["1102", "2949", "3101", "5102", "5405", "5667", "6108", "6404", "7101", "7949"] {
+            assert_eq!(tabela.contem(codigo), Some(true), "{}", codigo);
+        }
+        // 5.404 e 6.405 não existem: a ST interestadual com imposto já retido é só 6.404.
+        for codigo in ["5404", "6405", "1000", "5999"] {
+            assert_eq!(tabela.contem(codigo), Some(false), "{}", codigo);
+        }
+    }
+}
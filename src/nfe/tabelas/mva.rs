@@ -0,0 +1,57 @@
+use std::{collections::HashMap, error::Error, fs};
+
+use rust_decimal::Decimal;
+
+use crate::nfes::UF;
+
+/// MVA esperada por NCM e UF, carregada de um CSV externo (`NCM;UF;MVA`).
+///
+/// O NCM pode ser informado parcialmente (ex.: `2202` vale para todo o capítulo);
+/// a busca usa o prefixo mais longo cadastrado.
+#[derive(Debug, Default, Clone)]
+pub struct TabelaMva {
+    mva: HashMap<(UF, String), Decimal>,
+}
+
+impl TabelaMva {
+    /// Aceita `;` ou `,` como separador. Com `;`, a vírgula é tratada como separador decimal.
+    /// Uma primeira linha que não seja numérica é considerada cabeçalho.
+    pub fn carregar(caminho: &str) -> Result<TabelaMva, Box<dyn Error>> {
+        let conteudo: String = fs::read_to_string(caminho)?;
+        let mut tabela: TabelaMva = TabelaMva::default();
+
+        for (i, linha) in conteudo.lines().enumerate() {
+            let linha: &str = linha.trim();
+            if linha.is_empty() {
+                continue;
+            }
+            let separador: char = if linha.contains(';') { ';' } else { ',' };
+            let colunas: Vec<&str> = linha.split(separador).map(str::trim).collect();
+            let [ncm, uf, mva] = colunas[..] else {
+                return Err(format!("{}:{}: esperado NCM{}UF{}MVA", caminho, i + 1, separador, separador).into());
+            };
+
+            let mva: String = if separador == ';' { mva.replace(',', ".") } else { mva.to_string() };
+            let mva: Decimal = match mva.parse() {
+                Ok(mva) => mva,
+                Err(_) if i == 0 => continue,
+                Err(e) => return Err(format!("{}:{}: MVA '{}' inválida: {}", caminho, i + 1, mva, e).into()),
+            };
+            let uf: UF = UF::try_from(uf.to_uppercase().as_str())?;
+            let ncm: String = ncm.chars().filter(|c| c.is_ascii_digit()).collect();
+
+            tabela.mva.insert((uf, ncm), mva);
+        }
+        Ok(tabela)
+    }
+
+    pub fn buscar(&self, uf: UF, ncm: &str) -> Option<Decimal> {
+        if !ncm.is_ascii() {
+            return None;
+        }
+        (2..=ncm.len())
+            .rev()
+            .find_map(|n| self.mva.get(&(uf, ncm[..n].to_string())))
+            .copied()
+    }
+}
@@ -3,14 +3,16 @@
 use rust_decimal::Decimal;
 use serde::Serialize;
 
-use crate::nfes::{EmitenteId, UF};
+use crate::{nfe::codigos::ModFrete, nfes::{EmitenteId, UF}};
 
 
 
 #[derive(Debug, Default, Serialize)]
 pub struct Transp {
     /// Modalidade do frete.
-    pub modFrete: Decimal,
+    pub modFrete: ModFrete,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modFreteDesc: Option<&'static str>,
     pub transporta: Option<Transporta>,
     pub retTransp: Option<RetTransp>,
     #[serde(flatten)]
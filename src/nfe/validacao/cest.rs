@@ -0,0 +1,90 @@
+#![allow(non_snake_case)]
+use crate::{
+    nfe::{impostos::icms::Icms, tabelas::cest, validacao::Diagnostico},
+    nfes::{NFe, Tributacao},
+};
+
+const CEST_NCM_INCOMPATIVEL: &str = "CEST_NCM_INCOMPATIVEL";
+const CEST_AUSENTE: &str = "CEST_AUSENTE";
+
+/// Situações tributárias com ICMS-ST, que exigem o CEST.
+const CST_COM_ST: [&str; 4] = ["10", "30", "60", "70"];
+const CSOSN_COM_ST: [&str; 3] = ["201", "202", "500"];
+
+/// Confere o NCM do item com os NCMs permitidos para o CEST (Convênio ICMS 142/18)
+/// e exige o CEST nos itens com ICMS-ST.
+///
+/// A compatibilidade é conferida com os CESTs carregados: um CEST fora da tabela (a embutida
+/// é uma amostra do anexo) não gera diagnóstico, pois seus NCMs permitidos são desconhecidos.
+pub fn validar_nfe(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    for (i, det) in nfe.produtos.iter().enumerate() {
+        let prod = &det.produto;
+        let campo = format!("produtos[{}].produto.CEST", i);
+        let CEST: Option<&str> = prod.CEST.as_deref().map(str::trim).filter(|c| !c.is_empty());
+
+        match CEST {
+            Some(CEST) => {
+                if ncm_compativel(CEST, &prod.NCM) == Some(false) {
+                    out.push(Diagnostico::new(
+                        CEST_NCM_INCOMPATIVEL,
+                        campo,
+                        format!("CEST {} (segmento {}) não admite o NCM {}", CEST, &CEST[..2], prod.NCM),
+                    ));
+                }
+            }
+            None => {
+                if let Some(Tributacao::Mercadoria { ICMS, .. }) = &det.imposto.tributacao
+                    && possui_st(ICMS)
+                {
+                    out.push(Diagnostico::new(
+                        CEST_AUSENTE,
+                        campo,
+                        format!("Item com ICMS-ST ({}) sem CEST", get_situacao(ICMS)),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// `None` quando o CEST não consta da tabela carregada ou não traz a coluna de NCMs.
+fn ncm_compativel(CEST: &str, NCM: &str) -> Option<bool> {
+    let ncms: &String = cest().colunas(CEST)?.get(1)?;
+    Some(ncms.split_whitespace().any(|n| NCM.starts_with(n)))
+}
+
+fn possui_st(icms: &Icms) -> bool {
+    icms.CST.as_deref().is_some_and(|cst| CST_COM_ST.contains(&cst))
+        || icms.CSOSN.as_deref().is_some_and(|csosn| CSOSN_COM_ST.contains(&csosn))
+}
+
+fn get_situacao(icms: &Icms) -> String {
+    match (&icms.CST, &icms.CSOSN) {
+        (Some(cst), _) => format!("CST {}", cst),
+        (None, Some(csosn)) => format!("CSOSN {}", csosn),
+        (None, None) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aceita_ncm_permitido_para_o_cest() {
+        // Água mineral (CEST 03.001.00) com NCM 2201.10.00.
+        assert_eq!(ncm_compativel("0300100", "22011000"), Some(true));
+        assert_eq!(ncm_compativel("0302100", "22030000"), Some(true));
+    }
+
+    #[test]
+    fn rejeita_ncm_de_outro_cest() {
+        // Cerveja (CEST 03.021.00) com NCM de água mineral.
+        assert_eq!(ncm_compativel("0302100", "22011000"), Some(false));
+    }
+
+    #[test]
+    fn cest_fora_da_tabela_nao_e_conferido() {
+        assert_eq!(ncm_compativel("9999999", "22011000"), None);
+    }
+}
@@ -0,0 +1,140 @@
+#![allow(non_snake_case)]
+use time::OffsetDateTime;
+
+use crate::{
+    nfe::{
+        chave::ChaveAcesso,
+        codigos::CodigoSefaz,
+        eventos::evento::{Evento, EventoJson, InfEvento, TRetEvento},
+        validacao::Diagnostico,
+    },
+    nfes::{EmitenteId, NFRef, NFe},
+};
+
+const CHAVE_INVALIDA: &str = "CHAVE_INVALIDA";
+const CHAVE_DV_INVALIDO: &str = "CHAVE_DV_INVALIDO";
+const CHAVE_DIVERGENTE: &str = "CHAVE_DIVERGENTE";
+
+/// Confere o formato e o DV da chave de acesso e compara cada parte com `ide` e `emit`.
+pub fn validar_nfe(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    match &nfe.chave {
+        Some(chave) => {
+            checar_dv(chave, "infNFe.Id", out);
+            comparar_com_nfe(chave, nfe, out);
+        }
+        None => out.push(Diagnostico::new(
+            CHAVE_INVALIDA,
+            "infNFe.Id",
+            format!("Chave de acesso '{}' não possui 44 posições válidas", nfe.Id),
+        )),
+    }
+
+    if let (Some(prot), Some(chave)) = (&nfe.protNFe, &nfe.chave)
+        && prot.chNFe.trim() != chave.chave
+    {
+        out.push(Diagnostico::new(
+            CHAVE_DIVERGENTE,
+            "protNFe.chNFe",
+            format!("Chave do protocolo '{}' difere da chave da NF-e '{}'", prot.chNFe, chave.chave),
+        ));
+    }
+
+    for (i, nfref) in nfe.ide.NFref.iter().flatten().enumerate() {
+        match nfref {
+            NFRef::refNFe(ch) => checar_chave(ch, &format!("ide.NFref[{}].refNFe", i), out),
+            NFRef::refNFeSig(ch) => checar_chave(ch, &format!("ide.NFref[{}].refNFeSig", i), out),
+            _ => (),
+        }
+    }
+
+    for (i, ch) in nfe.ide.gPagAntecipado.iter().flatten().enumerate() {
+        checar_chave(ch, &format!("ide.gPagAntecipado[{}]", i), out);
+    }
+}
+
+pub fn validar_eventos(eventos: &EventoJson, out: &mut Vec<Diagnostico>) {
+    for (i, evento) in eventos.eventos.iter().enumerate() {
+        match evento {
+            Evento::evento(e) => checar_infEvento(&e.infEvento, &format!("eventos[{}]", i), out),
+            Evento::procEventoNFe(p) => {
+                checar_infEvento(&p.evento.infEvento, &format!("eventos[{}].evento", i), out);
+                checar_retEvento(&p.retEvento, &format!("eventos[{}].retEvento", i), out);
+            }
+            Evento::retEnvEvento(r) => checar_retEvento(r, &format!("eventos[{}]", i), out),
+        }
+    }
+}
+
+fn checar_infEvento(inf: &InfEvento, campo: &str, out: &mut Vec<Diagnostico>) {
+    checar_chave(&inf.chNFe, &format!("{}.chNFe", campo), out);
+    if !inf.chNFeRef.is_empty() {
+        checar_chave(&inf.chNFeRef, &format!("{}.chNFeRef", campo), out);
+    }
+}
+
+fn checar_retEvento(ret: &TRetEvento, campo: &str, out: &mut Vec<Diagnostico>) {
+    if let Some(ch) = &ret.chNFe {
+        checar_chave(ch, &format!("{}.chNFe", campo), out);
+    }
+}
+
+fn checar_chave(valor: &str, campo: &str, out: &mut Vec<Diagnostico>) {
+    match ChaveAcesso::decompor(valor) {
+        Some(chave) => checar_dv(&chave, campo, out),
+        None => out.push(Diagnostico::new(
+            CHAVE_INVALIDA,
+            campo,
+            format!("Chave de acesso '{}' não possui 44 posições válidas", valor),
+        )),
+    }
+}
+
+fn checar_dv(chave: &ChaveAcesso, campo: &str, out: &mut Vec<Diagnostico>) {
+    if !chave.dvValido {
+        out.push(Diagnostico::new(
+            CHAVE_DV_INVALIDO,
+            campo,
+            format!("Dígito verificador {} inválido para a chave {}", chave.cDV, chave.chave),
+        ));
+    }
+}
+
+fn comparar_com_nfe(chave: &ChaveAcesso, nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    let ide = &nfe.ide;
+    let mut divergencia = |campo: &str, na_chave: String, no_documento: String| {
+        if na_chave != no_documento {
+            out.push(Diagnostico::new(
+                CHAVE_DIVERGENTE,
+                campo,
+                format!("Chave de acesso contém '{}', documento contém '{}'", na_chave, no_documento),
+            ));
+        }
+    };
+
+    divergencia("ide.cUF", chave.cUF.to_string(), ide.cUF.to_string());
+    if let Some(dhEmi) = ide.dhEmi.valor {
+        divergencia("ide.dhEmi", chave.AAMM.clone(), get_AAMM(dhEmi));
+    }
+    if let Some(documento) = get_CNPJCPF(&nfe.emit.EmitenteId) {
+        divergencia("emit.CNPJ", chave.CNPJCPF.clone(), documento);
+    }
+    divergencia("ide.mod", chave.r#mod.to_string(), ide.r#mod.to_string());
+    divergencia("ide.serie", chave.serie.to_string(), ide.serie.to_string());
+    divergencia("ide.nNF", chave.nNF.to_string(), ide.nNF.to_string());
+    divergencia("ide.tpEmis", chave.tpEmis.to_string(), ide.tpEmis.codigo().to_string());
+    divergencia("ide.cNF", chave.cNF.clone(), ide.cNF.clone());
+    divergencia("ide.cDV", chave.cDV.to_string(), ide.cDV.to_string());
+}
+
+fn get_AAMM(dhEmi: OffsetDateTime) -> String {
+    format!("{:02}{:02}", dhEmi.year() % 100, dhEmi.month() as u8)
+}
+
+/// CNPJ/CPF como aparece na chave: o CPF é completado com zeros à esquerda.
+fn get_CNPJCPF(id: &EmitenteId) -> Option<String> {
+    match id {
+        EmitenteId::CNPJ(cnpj) => Some(cnpj.clone()),
+        EmitenteId::CPF(cpf) => Some(format!("{:0>14}", cpf)),
+        EmitenteId::idEstrangeiro(_) => None,
+    }
+}
@@ -0,0 +1,126 @@
+#![allow(non_snake_case)]
+use rust_decimal::Decimal;
+
+use crate::{
+    nfe::{
+        calculo::difal::{calcular_difal, MetodoDifal, ValoresDifal},
+        config::get_config,
+        validacao::{divergente, Diagnostico},
+    },
+    nfes::{NFe, Tributacao, UF},
+};
+
+const DIFAL_DIVERGENTE: &str = "DIFAL_DIVERGENTE";
+const DIFAL_TOTAL_DIVERGENTE: &str = "DIFAL_TOTAL_DIVERGENTE";
+const ALIQUOTA_INTERESTADUAL_INVALIDA: &str = "ALIQUOTA_INTERESTADUAL_INVALIDA";
+
+/// Recalcula o DIFAL e o FCP de cada ICMSUFDest e confere com o item e com o ICMSTot.
+///
+/// O método vem de `PARSER_DIFAL_BASE_DUPLA` pela UF do destinatário. Sem a lista
+/// configurada, a base dupla é presumida quando o vBCUFDest difere da base do ICMS do item,
+/// e os diagnósticos informam o método presumido.
+pub fn validar_nfe(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    let uf_dest: Option<UF> = nfe.dest.as_ref().and_then(|d| d.enderDest.as_ref()).and_then(|e| e.UF);
+    let base_dupla: &Vec<UF> = &get_config().difal_base_dupla;
+    let descrever = |metodo: MetodoDifal| -> String {
+        match base_dupla.is_empty() {
+            true => format!("{:?} presumida, sem PARSER_DIFAL_BASE_DUPLA", metodo),
+            false => format!("{:?}", metodo),
+        }
+    };
+    let mut metodos: Vec<MetodoDifal> = Vec::new();
+
+    let mut vFCPUFDest: Decimal = Decimal::ZERO;
+    let mut vICMSUFDest: Decimal = Decimal::ZERO;
+    let mut vICMSUFRemet: Decimal = Decimal::ZERO;
+    let mut possui_difal: bool = false;
+    // Item sem recálculo possível invalida a conferência do total
+    let mut todos_recalculados: bool = true;
+
+    for (i, det) in nfe.produtos.iter().enumerate() {
+        let Some(g) = &det.imposto.ICMSUFDest else {
+            continue;
+        };
+        let campo = format!("produtos[{}].imposto.ICMSUFDest", i);
+        possui_difal = true;
+
+        let Some(pICMSInter) = get_pICMSInter(&g.pICMSInter) else {
+            out.push(Diagnostico::new(
+                ALIQUOTA_INTERESTADUAL_INVALIDA,
+                format!("{}.pICMSInter", campo),
+                format!("Alíquota interestadual '{}' deve ser 4, 7 ou 12", g.pICMSInter),
+            ));
+            todos_recalculados = false;
+            continue;
+        };
+
+        let vBCOrigem: Option<Decimal> = match &det.imposto.tributacao {
+            Some(Tributacao::Mercadoria { ICMS, .. }) => ICMS.vBC,
+            _ => None,
+        };
+        let metodo: MetodoDifal = match (uf_dest, vBCOrigem) {
+            (Some(uf), _) if base_dupla.contains(&uf) => MetodoDifal::BaseDupla,
+            (_, Some(vBC)) if base_dupla.is_empty() && divergente(g.vBCUFDest, vBC) => MetodoDifal::BaseDupla,
+            _ => MetodoDifal::BaseUnica,
+        };
+
+        let v: ValoresDifal = calcular_difal(g, pICMSInter, vBCOrigem.unwrap_or(g.vBCUFDest), metodo);
+        let mut campos: Vec<(&str, Decimal, Decimal)> = vec![
+            ("vICMSUFDest", g.vICMSUFDest, v.vICMSUFDest),
+            ("vICMSUFRemet", g.vICMSUFRemet, v.vICMSUFRemet),
+        ];
+        // Na base única, o vBCUFDest informado é a própria base do cálculo e não é conferido;
+        // a base do FCP é conferida com ele.
+        if metodo == MetodoDifal::BaseDupla {
+            campos.push(("vBCUFDest", g.vBCUFDest, v.vBCUFDest));
+        }
+        if let Some(declarado) = g.vBCFCPUFDest {
+            campos.push(("vBCFCPUFDest", declarado, v.vBCFCPUFDest));
+        }
+        if let Some(declarado) = g.vFCPUFDest {
+            campos.push(("vFCPUFDest", declarado, v.vFCPUFDest));
+        }
+        for (nome, declarado, calculado) in campos {
+            if divergente(declarado, calculado) {
+                out.push(Diagnostico::new(
+                    DIFAL_DIVERGENTE,
+                    format!("{}.{}", campo, nome),
+                    format!("Informado {}, calculado {} ({})", declarado, calculado, descrever(metodo)),
+                ));
+            }
+        }
+
+        if !metodos.contains(&metodo) {
+            metodos.push(metodo);
+        }
+        vFCPUFDest += v.vFCPUFDest;
+        vICMSUFDest += v.vICMSUFDest;
+        vICMSUFRemet += v.vICMSUFRemet;
+    }
+
+    if !possui_difal || !todos_recalculados {
+        return;
+    }
+    let tot = &nfe.total.ICMSTot;
+    let totais = [
+        ("vFCPUFDest", tot.vFCPUFDest, vFCPUFDest),
+        ("vICMSUFDest", tot.vICMSUFDest, vICMSUFDest),
+        ("vICMSUFRemet", tot.vICMSUFRemet, vICMSUFRemet),
+    ];
+    let metodos: String = metodos.into_iter().map(descrever).collect::<Vec<String>>().join("; ");
+    for (nome, informado, calculado) in totais {
+        let informado: Decimal = informado.unwrap_or_default();
+        if divergente(informado, calculado) {
+            out.push(Diagnostico::new(
+                DIFAL_TOTAL_DIVERGENTE,
+                format!("total.ICMSTot.{}", nome),
+                format!("Total {} difere do DIFAL recalculado dos itens {} ({})", informado, calculado, metodos),
+            ));
+        }
+    }
+}
+
+fn get_pICMSInter(txt: &str) -> Option<Decimal> {
+    let p: Decimal = txt.trim().parse().ok()?;
+    [4, 7, 12].map(Decimal::from).contains(&p).then_some(p)
+}
@@ -0,0 +1,96 @@
+use crate::{
+    nfe::{
+        documento::{cnpj_valido, cpf_valido},
+        eventos::evento::{Evento, EventoJson},
+        validacao::Diagnostico,
+    },
+    nfes::{EmitenteId, NFRef, NFe},
+};
+
+const CNPJ_INVALIDO: &str = "CNPJ_INVALIDO";
+const CPF_INVALIDO: &str = "CPF_INVALIDO";
+
+/// Dígitos verificadores de todos os CNPJ/CPF do documento (participantes, transportador,
+/// autorizados, referências, pagamento, intermediador e responsável técnico).
+pub fn validar_nfe(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    checar_id(&nfe.emit.EmitenteId, "emit", out);
+    if let Some(dest) = &nfe.dest {
+        checar_id(&dest.EmitenteId, "dest", out);
+    }
+    if let Some(retirada) = &nfe.retirada {
+        checar_id(&retirada.EmitenteId, "retirada", out);
+    }
+    if let Some(entrega) = &nfe.entrega {
+        checar_id(&entrega.EmitenteId, "entrega", out);
+    }
+    for (i, id) in nfe.autXML.iter().flatten().enumerate() {
+        checar_id(id, &format!("autXML[{}]", i), out);
+    }
+    if let Some(avulsa) = &nfe.avulsa {
+        checar_cnpj(&avulsa.CNPJ, "avulsa.CNPJ", out);
+    }
+
+    for (i, nfref) in nfe.ide.NFref.iter().flatten().enumerate() {
+        match nfref {
+            NFRef::refNF(r) => checar_cnpj(&r.CNPJ, &format!("ide.NFref[{}].refNF.CNPJ", i), out),
+            NFRef::refNFP(r) => checar_id(&r.EmitenteId, &format!("ide.NFref[{}].refNFP", i), out),
+            _ => (),
+        }
+    }
+
+    if let Some(Some(transporta)) = nfe.transp.transporta.as_ref().map(|t| &t.identificacao) {
+        checar_id(transporta, "transp.transporta", out);
+    }
+
+    for (i, det_pag) in nfe.pag.detPag.iter().flatten().enumerate() {
+        let campo = format!("pag.detPag[{}]", i);
+        if let Some(cnpj) = &det_pag.CNPJPag {
+            checar_cnpj(cnpj, &format!("{}.CNPJPag", campo), out);
+        }
+        if let Some(card) = &det_pag.card {
+            if let Some(cnpj) = &card.CNPJ {
+                checar_cnpj(cnpj, &format!("{}.card.CNPJ", campo), out);
+            }
+            if let Some(cnpj) = &card.CNPJReceb {
+                checar_cnpj(cnpj, &format!("{}.card.CNPJReceb", campo), out);
+            }
+        }
+    }
+
+    if let Some(intermed) = &nfe.infIntermed {
+        checar_cnpj(&intermed.CNPJ, "infIntermed.CNPJ", out);
+    }
+    if let Some(resp) = &nfe.infRespTec {
+        checar_cnpj(&resp.CNPJ, "infRespTec.CNPJ", out);
+    }
+}
+
+pub fn validar_eventos(eventos: &EventoJson, out: &mut Vec<Diagnostico>) {
+    for (i, evento) in eventos.eventos.iter().enumerate() {
+        match evento {
+            Evento::evento(e) => checar_id(&e.infEvento.CpfCnpj, &format!("eventos[{}]", i), out),
+            Evento::procEventoNFe(p) => checar_id(&p.evento.infEvento.CpfCnpj, &format!("eventos[{}].evento", i), out),
+            Evento::retEnvEvento(_) => (),
+        }
+    }
+}
+
+fn checar_id(id: &EmitenteId, campo: &str, out: &mut Vec<Diagnostico>) {
+    match id {
+        EmitenteId::CNPJ(cnpj) => checar_cnpj(cnpj, &format!("{}.CNPJ", campo), out),
+        EmitenteId::CPF(cpf) => checar_cpf(cpf, &format!("{}.CPF", campo), out),
+        EmitenteId::idEstrangeiro(_) => (),
+    }
+}
+
+fn checar_cnpj(cnpj: &str, campo: &str, out: &mut Vec<Diagnostico>) {
+    if !cnpj_valido(cnpj) {
+        out.push(Diagnostico::new(CNPJ_INVALIDO, campo, format!("CNPJ '{}' inválido", cnpj)));
+    }
+}
+
+fn checar_cpf(cpf: &str, campo: &str, out: &mut Vec<Diagnostico>) {
+    if !cpf_valido(cpf) {
+        out.push(Diagnostico::new(CPF_INVALIDO, campo, format!("CPF '{}' inválido", cpf)));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{
+    nfe::{
+        codigos::CodigoSefaz,
+        data_hora::{Data, DataHora},
+        eventos::evento::{Evento, EventoJson},
+        impostos::icms::Icms,
+        validacao::Diagnostico,
+    },
+    nfes::{NFe, Tributacao},
+};
+
+const CODIGO_DESCONHECIDO: &str = "CODIGO_DESCONHECIDO";
+const DATA_INVALIDA: &str = "DATA_INVALIDA";
+
+/// Campos codificados fora da tabela SEFAZ e datas que não puderam ser interpretadas.
+pub fn validar_nfe(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    let ide = &nfe.ide;
+    checar_codigo(&ide.finNFe, "ide.finNFe", out);
+    checar_codigo(&ide.indPres, "ide.indPres", out);
+    checar_codigo(&ide.tpEmis, "ide.tpEmis", out);
+    checar_data_hora(&ide.dhEmi, "ide.dhEmi", out);
+    if let Some(dh) = &ide.dhSaiEnt {
+        checar_data_hora(dh, "ide.dhSaiEnt", out);
+    }
+    if let Some(dh) = &ide.dhCont {
+        checar_data_hora(dh, "ide.dhCont", out);
+    }
+
+    checar_codigo(&nfe.emit.CRT, "emit.CRT", out);
+    if let Some(dest) = &nfe.dest {
+        checar_codigo(&dest.indIEDest, "dest.indIEDest", out);
+    }
+
+    for (i, det) in nfe.produtos.iter().enumerate() {
+        if let Some(Tributacao::Mercadoria { ICMS, .. }) = &det.imposto.tributacao {
+            checar_icms(ICMS, &format!("produtos[{}].imposto.ICMS", i), out);
+        }
+    }
+
+    checar_codigo(&nfe.transp.modFrete, "transp.modFrete", out);
+
+    if let Some(dups) = nfe.cobr.as_ref().and_then(|c| c.dup.as_ref()) {
+        for (i, dup) in dups.iter().enumerate() {
+            if let Some(d) = &dup.dVenc {
+                checar_data(d, &format!("cobr.dup[{}].dVenc", i), out);
+            }
+        }
+    }
+
+    for (i, det_pag) in nfe.pag.detPag.iter().flatten().enumerate() {
+        checar_codigo(&det_pag.tPag, &format!("pag.detPag[{}].tPag", i), out);
+        if let Some(d) = &det_pag.dPag {
+            checar_data(d, &format!("pag.detPag[{}].dPag", i), out);
+        }
+    }
+
+    if let Some(prot) = &nfe.protNFe {
+        checar_data_hora(&prot.dhRecbto, "protNFe.dhRecbto", out);
+    }
+}
+
+pub fn validar_eventos(eventos: &EventoJson, out: &mut Vec<Diagnostico>) {
+    for (i, evento) in eventos.eventos.iter().enumerate() {
+        match evento {
+            Evento::evento(e) => {
+                checar_data_hora(&e.infEvento.dhEvento, &format!("eventos[{}].dhEvento", i), out);
+            }
+            Evento::procEventoNFe(p) => {
+                checar_data_hora(&p.evento.infEvento.dhEvento, &format!("eventos[{}].evento.dhEvento", i), out);
+                checar_data_hora(&p.retEvento.dhRegEvento, &format!("eventos[{}].retEvento.dhRegEvento", i), out);
+            }
+            Evento::retEnvEvento(r) => {
+                checar_data_hora(&r.dhRegEvento, &format!("eventos[{}].dhRegEvento", i), out);
+            }
+        }
+    }
+}
+
+fn checar_icms(icms: &Icms, campo: &str, out: &mut Vec<Diagnostico>) {
+    if let Some(c) = &icms.modBC {
+        checar_codigo(c, &format!("{}.modBC", campo), out);
+    }
+    if let Some(c) = &icms.modBCST {
+        checar_codigo(c, &format!("{}.modBCST", campo), out);
+    }
+    if let Some(c) = &icms.motDesICMS {
+        checar_codigo(c, &format!("{}.motDesICMS", campo), out);
+    }
+    if let Some(c) = &icms.motDesICMSST {
+        checar_codigo(c, &format!("{}.motDesICMSST", campo), out);
+    }
+}
+
+fn checar_codigo<T: CodigoSefaz>(codigo: &T, campo: &str, out: &mut Vec<Diagnostico>) {
+    if codigo.is_desconhecido() {
+        out.push(Diagnostico::new(
+            CODIGO_DESCONHECIDO,
+            campo,
+            format!("Código '{}' não pertence à tabela do campo", codigo.codigo()),
+        ));
+    }
+}
+
+fn checar_data_hora(dh: &DataHora, campo: &str, out: &mut Vec<Diagnostico>) {
+    if dh.valor.is_none() {
+        out.push(Diagnostico::new(DATA_INVALIDA, campo, format!("Data/hora inválida: '{}'", dh.original)));
+    }
+}
+
+fn checar_data(d: &Data, campo: &str, out: &mut Vec<Diagnostico>) {
+    if d.valor.is_none() {
+        out.push(Diagnostico::new(DATA_INVALIDA, campo, format!("Data inválida: '{}'", d.original)));
+    }
+}
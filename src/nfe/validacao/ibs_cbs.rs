@@ -0,0 +1,271 @@
+#![allow(non_snake_case)]
+use rust_decimal::Decimal;
+
+use crate::{
+    nfe::{
+        calculo::{arredondar, ibs_cbs::{calcular_cibs, calcular_monofasia, get_valor_cred_pres, Tributo, ValoresIBSCBS}, percentual},
+        impostos::{
+            cibs::{TCIBS, TCredPres, TDevTrib, TDif, TRed, TTribCompraGov, TTribRegular, ValorCredPres},
+            ibs_cbs::TributacaoIBS,
+        },
+        total::IBSCBSTot,
+        validacao::{divergente, Diagnostico},
+    },
+    nfes::NFe,
+};
+
+const IBSCBS_DIVERGENTE: &str = "IBSCBS_DIVERGENTE";
+const IBSCBSTOT_DIVERGENTE: &str = "IBSCBSTOT_DIVERGENTE";
+
+/// Somatório dos valores recalculados, comparado com o IBSCBSTot.
+#[derive(Default)]
+struct Somatorio {
+    vBC: Decimal,
+    vDifUF: Decimal,
+    vDevTribUF: Decimal,
+    vIBSUF: Decimal,
+    vDifMun: Decimal,
+    vDevTribMun: Decimal,
+    vIBSMun: Decimal,
+    vIBS: Decimal,
+    vIBSCredPres: Decimal,
+    vIBSCredPresCondSus: Decimal,
+    vDifCBS: Decimal,
+    vDevTribCBS: Decimal,
+    vCBS: Decimal,
+    vCBSCredPres: Decimal,
+    vCBSCredPresCondSus: Decimal,
+    vIBSMono: Decimal,
+    vCBSMono: Decimal,
+    vIBSMonoReten: Decimal,
+    vCBSMonoReten: Decimal,
+    vIBSMonoRet: Decimal,
+    vCBSMonoRet: Decimal,
+}
+
+/// Recalcula IBS/CBS de cada item e confere o resultado com os valores informados e com o IBSCBSTot.
+pub fn validar_nfe(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    let mut s: Somatorio = Somatorio::default();
+
+    for (i, det) in nfe.produtos.iter().enumerate() {
+        let Some(ibscbs) = &det.imposto.IBSCBS else {
+            continue;
+        };
+        let campo = format!("produtos[{}].imposto.IBSCBS", i);
+        match &ibscbs.tributacao {
+            Some(TributacaoIBS::gIBSCBS(g)) => validar_cibs(g, &format!("{}.gIBSCBS", campo), &mut s, out),
+            Some(TributacaoIBS::gIBSCBSMono(m)) => {
+                let campo = format!("{}.gIBSCBSMono", campo);
+                let v = calcular_monofasia(m);
+                let grupos = [
+                    ("gMonoPadrao.vIBSMono", m.gMonoPadrao.as_ref().map(|g| g.vIBSMono), v.vIBSMono),
+                    ("gMonoPadrao.vCBSMono", m.gMonoPadrao.as_ref().map(|g| g.vCBSMono), v.vCBSMono),
+                    ("gMonoReten.vIBSMonoReten", m.gMonoReten.as_ref().map(|g| g.vIBSMonoReten), v.vIBSMonoReten),
+                    ("gMonoReten.vCBSMonoReten", m.gMonoReten.as_ref().map(|g| g.vCBSMonoReten), v.vCBSMonoReten),
+                    ("gMonoRet.vIBSMonoRet", m.gMonoRet.as_ref().map(|g| g.vIBSMonoRet), v.vIBSMonoRet),
+                    ("gMonoRet.vCBSMonoRet", m.gMonoRet.as_ref().map(|g| g.vCBSMonoRet), v.vCBSMonoRet),
+                    ("gMonoDif.vIBSMonoDif", m.gMonoDif.as_ref().map(|g| g.vIBSMonoDif), v.vIBSMonoDif),
+                    ("gMonoDif.vCBSMonoDif", m.gMonoDif.as_ref().map(|g| g.vCBSMonoDif), v.vCBSMonoDif),
+                    ("vTotIBSMonoItem", Some(m.vTotIBSMonoItem), v.vTotIBSMonoItem),
+                    ("vTotCBSMonoItem", Some(m.vTotCBSMonoItem), v.vTotCBSMonoItem),
+                ];
+                for (nome, declarado, calculado) in grupos {
+                    if let Some(declarado) = declarado {
+                        comparar_item(&format!("{}.{}", campo, nome), declarado, calculado, out);
+                    }
+                }
+
+                s.vIBSMono += v.vIBSMono;
+                s.vCBSMono += v.vCBSMono;
+                s.vIBSMonoReten += v.vIBSMonoReten;
+                s.vCBSMonoReten += v.vCBSMonoReten;
+                s.vIBSMonoRet += v.vIBSMonoRet;
+                s.vCBSMonoRet += v.vCBSMonoRet;
+            }
+            Some(TributacaoIBS::gTransfCred(_)) | None => (),
+        }
+    }
+
+    if let Some(tot) = &nfe.total.IBSCBSTot {
+        validar_total(tot, &s, out);
+    }
+}
+
+fn validar_cibs(g: &TCIBS, campo: &str, s: &mut Somatorio, out: &mut Vec<Diagnostico>) {
+    // Na compra governamental, os valores do item seguem o cálculo padrão com as alíquotas já
+    // reduzidas; o grupo traz as alíquotas integrais, conferidas sobre a mesma base.
+    if let Some(gov) = &g.gTribCompraGov {
+        validar_compra_gov(gov, g.vBC, &format!("{}.gTribCompraGov", campo), out);
+    }
+    // Com tributação regular, os valores do item não seguem o cálculo padrão: confere-se apenas
+    // o grupo, e o total usa os valores informados.
+    if let Some(reg) = &g.gTribRegular {
+        validar_trib_regular(reg, g.vBC, &format!("{}.gTribRegular", campo), out);
+        log::debug!("{}: item com gTribRegular, IBS/CBS somados ao IBSCBSTot sem recálculo", campo);
+        somar_informados(g, s);
+        return;
+    }
+
+    let v: ValoresIBSCBS = calcular_cibs(g);
+
+    comparar_tributo(&format!("{}.gIBSUF", campo), "vIBSUF", g.gIBSUF.vIBSUF, &g.gIBSUF.gRed, &g.gIBSUF.gDif, &v.vIBSUF, out);
+    comparar_tributo(&format!("{}.gIBSMun", campo), "vIBSMun", g.gIBSMun.vIBSMun, &g.gIBSMun.gRed, &g.gIBSMun.gDif, &v.vIBSMun, out);
+    comparar_tributo(&format!("{}.gCBS", campo), "vCBS", g.gCBS.vCBS, &g.gCBS.gRed, &g.gCBS.gDif, &v.vCBS, out);
+    comparar_item(&format!("{}.vIBS", campo), g.vIBS, v.vIBS, out);
+
+    if let (Some(cred), Some(calculado)) = (&g.gIBSCredPres, v.vIBSCredPres) {
+        comparar_item(&format!("{}.gIBSCredPres", campo), get_valor_cred_pres(cred), calculado, out);
+        somar_cred_pres(cred, calculado, &mut s.vIBSCredPres, &mut s.vIBSCredPresCondSus);
+    }
+    if let (Some(cred), Some(calculado)) = (&g.gCBSCredPres, v.vCBSCredPres) {
+        comparar_item(&format!("{}.gCBSCredPres", campo), get_valor_cred_pres(cred), calculado, out);
+        somar_cred_pres(cred, calculado, &mut s.vCBSCredPres, &mut s.vCBSCredPresCondSus);
+    }
+
+    s.vBC += g.vBC;
+    s.vDifUF += v.vIBSUF.vDif;
+    s.vDevTribUF += get_vDevTrib(&g.gIBSUF.gDevTrib);
+    s.vIBSUF += v.vIBSUF.valor;
+    s.vDifMun += v.vIBSMun.vDif;
+    s.vDevTribMun += get_vDevTrib(&g.gIBSMun.gDevTrib);
+    s.vIBSMun += v.vIBSMun.valor;
+    s.vIBS += v.vIBS;
+    s.vDifCBS += v.vCBS.vDif;
+    s.vDevTribCBS += get_vDevTrib(&g.gCBS.gDevTrib);
+    s.vCBS += v.vCBS.valor;
+}
+
+/// Valor da tributação regular = vBC × alíquota efetiva regular, para cada tributo.
+fn validar_trib_regular(reg: &TTribRegular, vBC: Decimal, campo: &str, out: &mut Vec<Diagnostico>) {
+    let tributos = [
+        ("vTribRegIBSUF", reg.vTribRegIBSUF, reg.pAliqEfetRegIBSUF),
+        ("vTribRegIBSMun", reg.vTribRegIBSMun, reg.pAliqEfetRegIBSMun),
+        ("vTribRegCBS", reg.vTribRegCBS, reg.pAliqEfetRegCBS),
+    ];
+    for (nome, declarado, aliquota) in tributos {
+        comparar_item(&format!("{}.{}", campo, nome), declarado, arredondar(percentual(vBC, aliquota)), out);
+    }
+}
+
+/// Valor de cada tributo na compra governamental = vBC × alíquota informada no grupo.
+fn validar_compra_gov(gov: &TTribCompraGov, vBC: Decimal, campo: &str, out: &mut Vec<Diagnostico>) {
+    let tributos = [
+        ("vTribIBSUF", gov.vTribIBSUF, gov.pAliqIBSUF),
+        ("vTribIBSMun", gov.vTribIBSMun, gov.pAliqIBSMun),
+        ("vTribCBS", gov.vTribCBS, gov.pAliqCBS),
+    ];
+    for (nome, declarado, aliquota) in tributos {
+        comparar_item(&format!("{}.{}", campo, nome), declarado, arredondar(percentual(vBC, aliquota)), out);
+    }
+}
+
+/// Soma os valores informados no item, para itens com tributação regular.
+fn somar_informados(g: &TCIBS, s: &mut Somatorio) {
+    let vDif = |gDif: &Option<TDif>| gDif.as_ref().map(|d| d.vDif).unwrap_or_default();
+
+    if let Some(cred) = &g.gIBSCredPres {
+        somar_cred_pres(cred, get_valor_cred_pres(cred), &mut s.vIBSCredPres, &mut s.vIBSCredPresCondSus);
+    }
+    if let Some(cred) = &g.gCBSCredPres {
+        somar_cred_pres(cred, get_valor_cred_pres(cred), &mut s.vCBSCredPres, &mut s.vCBSCredPresCondSus);
+    }
+
+    s.vBC += g.vBC;
+    s.vDifUF += vDif(&g.gIBSUF.gDif);
+    s.vDevTribUF += get_vDevTrib(&g.gIBSUF.gDevTrib);
+    s.vIBSUF += g.gIBSUF.vIBSUF;
+    s.vDifMun += vDif(&g.gIBSMun.gDif);
+    s.vDevTribMun += get_vDevTrib(&g.gIBSMun.gDevTrib);
+    s.vIBSMun += g.gIBSMun.vIBSMun;
+    s.vIBS += g.vIBS;
+    s.vDifCBS += vDif(&g.gCBS.gDif);
+    s.vDevTribCBS += get_vDevTrib(&g.gCBS.gDevTrib);
+    s.vCBS += g.gCBS.vCBS;
+}
+
+fn comparar_tributo(
+    campo: &str,
+    nome: &str,
+    declarado: Decimal,
+    gRed: &Option<TRed>,
+    gDif: &Option<TDif>,
+    calculado: &Tributo,
+    out: &mut Vec<Diagnostico>,
+) {
+    if let Some(red) = gRed
+        && red.pAliqEfet.round_dp(4) != calculado.pAliqEfet.round_dp(4)
+    {
+        out.push(Diagnostico::new(
+            IBSCBS_DIVERGENTE,
+            format!("{}.gRed.pAliqEfet", campo),
+            format!("Informado {}, calculado {}", red.pAliqEfet, calculado.pAliqEfet.round_dp(4)),
+        ));
+    }
+    if let Some(dif) = gDif {
+        comparar_item(&format!("{}.gDif.vDif", campo), dif.vDif, calculado.vDif, out);
+    }
+    comparar_item(&format!("{}.{}", campo, nome), declarado, calculado.valor, out);
+}
+
+fn comparar_item(campo: &str, declarado: Decimal, calculado: Decimal, out: &mut Vec<Diagnostico>) {
+    if divergente(declarado, calculado) {
+        out.push(Diagnostico::new(IBSCBS_DIVERGENTE, campo, format!("Informado {}, calculado {}", declarado, calculado)));
+    }
+}
+
+fn somar_cred_pres(cred: &TCredPres, calculado: Decimal, vCredPres: &mut Decimal, vCredPresCondSus: &mut Decimal) {
+    match cred.valor {
+        ValorCredPres::vCredPres(_) => *vCredPres += calculado,
+        ValorCredPres::vCredPresCondSus(_) => *vCredPresCondSus += calculado,
+    }
+}
+
+fn get_vDevTrib(gDevTrib: &Option<TDevTrib>) -> Decimal {
+    gDevTrib.as_ref().map(|d| d.vDevTrib).unwrap_or_default()
+}
+
+fn validar_total(tot: &IBSCBSTot, s: &Somatorio, out: &mut Vec<Diagnostico>) {
+    let mut campos: Vec<(&str, Decimal, Decimal)> = vec![("vBCIBSCBS", tot.vBCIBSCBS, s.vBC)];
+    if let Some(g) = &tot.gIBS {
+        campos.extend([
+            ("gIBS.gIBSUF.vDif", g.gIBSUF.vDif, s.vDifUF),
+            ("gIBS.gIBSUF.vDevTrib", g.gIBSUF.vDevTrib, s.vDevTribUF),
+            ("gIBS.gIBSUF.vIBSUF", g.gIBSUF.vIBSUF, s.vIBSUF),
+            ("gIBS.gIBSMun.vDif", g.gIBSMun.vDif, s.vDifMun),
+            ("gIBS.gIBSMun.vDevTrib", g.gIBSMun.vDevTrib, s.vDevTribMun),
+            ("gIBS.gIBSMun.vIBSMun", g.gIBSMun.vIBSMun, s.vIBSMun),
+            ("gIBS.vIBS", g.vIBS, s.vIBS),
+            ("gIBS.vCredPres", g.vCredPres, s.vIBSCredPres),
+            ("gIBS.vCredPresCondSus", g.vCredPresCondSus, s.vIBSCredPresCondSus),
+        ]);
+    }
+    if let Some(g) = &tot.gCBS {
+        campos.extend([
+            ("gCBS.vDif", g.vDif, s.vDifCBS),
+            ("gCBS.vDevTrib", g.vDevTrib, s.vDevTribCBS),
+            ("gCBS.vCBS", g.vCBS, s.vCBS),
+            ("gCBS.vCredPres", g.vCredPres, s.vCBSCredPres),
+            ("gCBS.vCredPresCondSus", g.vCredPresCondSus, s.vCBSCredPresCondSus),
+        ]);
+    }
+    if let Some(g) = &tot.gMono {
+        campos.extend([
+            ("gMono.vIBSMono", g.vIBSMono, s.vIBSMono),
+            ("gMono.vCBSMono", g.vCBSMono, s.vCBSMono),
+            ("gMono.vIBSMonoReten", g.vIBSMonoReten, s.vIBSMonoReten),
+            ("gMono.vCBSMonoReten", g.vCBSMonoReten, s.vCBSMonoReten),
+            ("gMono.vIBSMonoRet", g.vIBSMonoRet, s.vIBSMonoRet),
+            ("gMono.vCBSMonoRet", g.vCBSMonoRet, s.vCBSMonoRet),
+        ]);
+    }
+
+    for (campo, informado, calculado) in campos {
+        if divergente(informado, calculado) {
+            out.push(Diagnostico::new(
+                IBSCBSTOT_DIVERGENTE,
+                format!("total.IBSCBSTot.{}", campo),
+                format!("Total {} difere do somatório recalculado dos itens {}", informado, calculado),
+            ));
+        }
+    }
+}
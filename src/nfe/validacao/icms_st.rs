@@ -0,0 +1,107 @@
+#![allow(non_snake_case)]
+use rust_decimal::Decimal;
+
+use crate::{
+    nfe::{
+        calculo::icms_st::{calcular_icms_st, mva_ajustada, ValoresST},
+        codigos::ModBCST,
+        config::get_config,
+        impostos::ipi,
+        validacao::{divergente, Diagnostico},
+    },
+    nfes::{Det, NFe, Tributacao, UF},
+};
+
+const ICMSST_DIVERGENTE: &str = "ICMSST_DIVERGENTE";
+const MVA_DIVERGENTE: &str = "MVA_DIVERGENTE";
+
+/// Recalcula base e valor do ICMS-ST de cada item. Com `PARSER_MVA_CSV`, confere também a MVA
+/// informada com a tabela por NCM e UF (de destino, ou do emitente em operações internas). Em
+/// operações interestaduais a MVA da tabela é ajustada pelas alíquotas do item.
+pub fn validar_nfe(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    let uf: Option<UF> = nfe
+        .dest
+        .as_ref()
+        .and_then(|d| d.enderDest.as_ref())
+        .and_then(|e| e.UF)
+        .or(nfe.emit.enderEmit.UF);
+    let interestadual: bool = nfe.ide.idDest == 2;
+
+    for (i, det) in nfe.produtos.iter().enumerate() {
+        let Some(Tributacao::Mercadoria { ICMS, IPI, .. }) = &det.imposto.tributacao else {
+            continue;
+        };
+        let campo = format!("produtos[{}].imposto.ICMS", i);
+
+        let vIPI: Decimal = match IPI.as_ref().map(|ipi| &ipi.Tributacao) {
+            Some(ipi::Tributacao::IPITrib(t)) => t.vIPI,
+            _ => Decimal::ZERO,
+        };
+        let Some(v) = calcular_icms_st(ICMS, get_valor_item(det) + vIPI) else {
+            continue;
+        };
+        let ValoresST { vBCST, vICMSST, vFCPST } = v;
+
+        let campos = [
+            ("vBCST", ICMS.vBCST, vBCST),
+            ("vICMSST", ICMS.vICMSST, vICMSST),
+            ("vFCPST", ICMS.vFCPST, vFCPST),
+        ];
+        for (nome, declarado, calculado) in campos {
+            if let (Some(declarado), Some(calculado)) = (declarado, calculado)
+                && divergente(declarado, calculado)
+            {
+                out.push(Diagnostico::new(
+                    ICMSST_DIVERGENTE,
+                    format!("{}.{}", campo, nome),
+                    format!("Informado {}, calculado {}", declarado, calculado),
+                ));
+            }
+        }
+
+        if let (Some(tabela), Some(uf), Some(ModBCST::Mva), Some(pMVAST)) =
+            (&get_config().tabela_mva, uf, &ICMS.modBCST, ICMS.pMVAST)
+            && let Some(original) = tabela.buscar(uf, &det.produto.NCM)
+            && let Some(esperada) = get_mva_esperada(original, interestadual, ICMS.pICMS, ICMS.pICMSST)
+            && divergente(pMVAST, esperada)
+        {
+            out.push(Diagnostico::new(
+                MVA_DIVERGENTE,
+                format!("{}.pMVAST", campo),
+                format!("MVA {} difere da tabela ({}) para NCM {} / {:?}", pMVAST, esperada, det.produto.NCM, uf),
+            ));
+        }
+    }
+}
+
+/// Sem as alíquotas do item não há como ajustar a MVA de uma operação interestadual.
+fn get_mva_esperada(original: Decimal, interestadual: bool, pICMS: Option<Decimal>, pICMSST: Option<Decimal>) -> Option<Decimal> {
+    if !interestadual {
+        return Some(original);
+    }
+    Some(mva_ajustada(original, pICMS?, pICMSST?))
+}
+
+fn get_valor_item(det: &Det) -> Decimal {
+    let p = &det.produto;
+    p.vProd + p.vFrete.unwrap_or_default() + p.vSeg.unwrap_or_default() + p.vOutro.unwrap_or_default()
+        - p.vDesc.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(valor: &str) -> Decimal {
+        valor.parse().unwrap()
+    }
+
+    #[test]
+    fn ajusta_a_mva_da_tabela_so_na_operacao_interestadual() {
+        let (pICMS, pICMSST) = (Some(d("12.00")), Some(d("18.00")));
+        assert_eq!(get_mva_esperada(d("40.00"), false, pICMS, pICMSST), Some(d("40.00")));
+        assert_eq!(get_mva_esperada(d("40.00"), true, pICMS, pICMSST), Some(d("50.24")));
+        // Sem a alíquota interestadual do item a MVA não é conferida.
+        assert_eq!(get_mva_esperada(d("40.00"), true, None, pICMSST), None);
+    }
+}
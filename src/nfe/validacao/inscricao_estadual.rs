@@ -0,0 +1,52 @@
+use crate::{
+    nfe::{codigos::IndIEDest, inscricao_estadual::ie_valida, validacao::Diagnostico},
+    nfes::{NFe, UF},
+};
+
+const IE_INVALIDA: &str = "IE_INVALIDA";
+const IE_INDICADOR_DIVERGENTE: &str = "IE_INDICADOR_DIVERGENTE";
+
+/// IEs conferidas pelo algoritmo da UF do respectivo endereço. O IEST do emitente
+/// pertence à UF de destino.
+pub fn validar_nfe(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    let uf_dest: Option<UF> = nfe.dest.as_ref().and_then(|d| d.enderDest.as_ref()).and_then(|e| e.UF);
+
+    checar_ie(Some(&nfe.emit.IE), nfe.emit.enderEmit.UF, "emit.IE", out);
+    checar_ie(nfe.emit.IEST.as_deref(), uf_dest, "emit.IEST", out);
+
+    if let Some(dest) = &nfe.dest {
+        checar_ie(dest.IE.as_deref(), uf_dest, "dest.IE", out);
+        checar_indIEDest(&dest.indIEDest, dest.IE.as_deref(), out);
+    }
+    if let Some(retirada) = &nfe.retirada {
+        checar_ie(retirada.IE.as_deref(), retirada.UF, "retirada.IE", out);
+    }
+    if let Some(entrega) = &nfe.entrega {
+        checar_ie(entrega.IE.as_deref(), entrega.UF, "entrega.IE", out);
+    }
+    if let Some(transporta) = &nfe.transp.transporta {
+        checar_ie(transporta.IE.as_deref(), transporta.UF, "transp.transporta.IE", out);
+    }
+}
+
+fn checar_ie(ie: Option<&str>, uf: Option<UF>, campo: &str, out: &mut Vec<Diagnostico>) {
+    if let (Some(ie), Some(uf)) = (ie, uf)
+        && !ie_valida(uf, ie)
+    {
+        out.push(Diagnostico::new(IE_INVALIDA, campo, format!("IE '{}' inválida para a UF {:?}", ie, uf)));
+    }
+}
+
+/// Contribuinte (1) exige IE; isento (2) e não contribuinte (9) não devem informá-la.
+#[allow(non_snake_case)]
+fn checar_indIEDest(indIEDest: &IndIEDest, ie: Option<&str>, out: &mut Vec<Diagnostico>) {
+    let informada: bool = ie.is_some_and(|ie| !ie.trim().is_empty() && !ie.trim().eq_ignore_ascii_case("ISENTO"));
+
+    let mensagem: &str = match indIEDest {
+        IndIEDest::Contribuinte if !informada => "indIEDest=1 (contribuinte) sem IE informada",
+        IndIEDest::ContribuinteIsento if informada => "indIEDest=2 (isento) com IE informada",
+        IndIEDest::NaoContribuinte if informada => "indIEDest=9 (não contribuinte) com IE informada",
+        _ => return,
+    };
+    out.push(Diagnostico::new(IE_INDICADOR_DIVERGENTE, "dest.indIEDest", mensagem));
+}
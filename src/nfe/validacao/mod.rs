@@ -0,0 +1,67 @@
+pub mod cest;
+pub mod chave;
+pub mod difal;
+pub mod documento;
+pub mod formato;
+pub mod ibs_cbs;
+pub mod icms_st;
+pub mod inscricao_estadual;
+pub mod tabelas;
+pub mod totais;
+pub mod uf;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{nfe::{config::get_config, eventos::evento::EventoJson}, nfes::NFe};
+
+/// Inconsistência encontrada no documento, publicada junto do JSON em vez de rejeitá-lo.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostico {
+    pub regra: &'static str,
+    pub campo: String,
+    pub mensagem: String,
+}
+
+impl Diagnostico {
+    pub fn new(regra: &'static str, campo: impl Into<String>, mensagem: impl Into<String>) -> Self {
+        Self {
+            regra,
+            campo: campo.into(),
+            mensagem: mensagem.into(),
+        }
+    }
+}
+
+/// Diferença aceita entre o valor informado e o recalculado (arredondamento por item).
+pub const TOLERANCIA: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+
+pub fn divergente(informado: Decimal, calculado: Decimal) -> bool {
+    (informado - calculado).abs() > TOLERANCIA
+}
+
+pub fn validar_nfe(nfe: &NFe) -> Vec<Diagnostico> {
+    let mut diagnosticos: Vec<Diagnostico> = Vec::new();
+    formato::validar_nfe(nfe, &mut diagnosticos);
+    uf::validar_nfe(nfe, &mut diagnosticos);
+    chave::validar_nfe(nfe, &mut diagnosticos);
+    documento::validar_nfe(nfe, &mut diagnosticos);
+    inscricao_estadual::validar_nfe(nfe, &mut diagnosticos);
+    tabelas::validar_nfe(nfe, &mut diagnosticos);
+    cest::validar_nfe(nfe, &mut diagnosticos);
+    if get_config().validar_totais {
+        totais::validar_nfe(nfe, &mut diagnosticos);
+        ibs_cbs::validar_nfe(nfe, &mut diagnosticos);
+        difal::validar_nfe(nfe, &mut diagnosticos);
+        icms_st::validar_nfe(nfe, &mut diagnosticos);
+    }
+    diagnosticos
+}
+
+pub fn validar_eventos(eventos: &EventoJson) -> Vec<Diagnostico> {
+    let mut diagnosticos: Vec<Diagnostico> = Vec::new();
+    formato::validar_eventos(eventos, &mut diagnosticos);
+    chave::validar_eventos(eventos, &mut diagnosticos);
+    documento::validar_eventos(eventos, &mut diagnosticos);
+    diagnosticos
+}
@@ -0,0 +1,135 @@
+#![allow(non_snake_case)]
+use crate::{
+    nfe::{
+        tabelas::{cclasstrib, cest, cfop, municipio_valido, municipios, ncm, Tabela},
+        validacao::Diagnostico,
+    },
+    nfes::{NFe, UF},
+};
+
+const CFOP_INVALIDO: &str = "CFOP_INVALIDO";
+const NCM_INVALIDO: &str = "NCM_INVALIDO";
+const CEST_INVALIDO: &str = "CEST_INVALIDO";
+const CCLASSTRIB_INVALIDO: &str = "CCLASSTRIB_INVALIDO";
+const MUNICIPIO_INVALIDO: &str = "MUNICIPIO_INVALIDO";
+const MUNICIPIO_UF_DIVERGENTE: &str = "MUNICIPIO_UF_DIVERGENTE";
+const MUNICIPIO_NOME_DIVERGENTE: &str = "MUNICIPIO_NOME_DIVERGENTE";
+
+/// Código de município do exterior.
+const MUNICIPIO_EXTERIOR: u32 = 9999999;
+
+/// Formato dos códigos e, quando a tabela embutida o conhece, a existência do código
+/// e o nome do município.
+pub fn validar_nfe(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    for (i, det) in nfe.produtos.iter().enumerate() {
+        let prod = &det.produto;
+        let campo = format!("produtos[{}]", i);
+
+        let cfop_formato: bool = prod.CFOP.len() == 4 && digitos(&prod.CFOP) && matches!(prod.CFOP.as_bytes()[0], b'1'..=b'3' | b'5'..=b'7');
+        checar_codigo(cfop, CFOP_INVALIDO, &prod.CFOP, cfop_formato, &format!("{}.produto.CFOP", campo), out);
+
+        // "00" é aceito para itens que não são mercadorias
+        let ncm_formato: bool = digitos(&prod.NCM) && (prod.NCM.len() == 8 || prod.NCM == "00");
+        checar_codigo(ncm, NCM_INVALIDO, &prod.NCM, ncm_formato, &format!("{}.produto.NCM", campo), out);
+
+        if let Some(CEST) = &prod.CEST {
+            let formato: bool = CEST.len() == 7 && digitos(CEST);
+            checar_codigo(cest, CEST_INVALIDO, CEST, formato, &format!("{}.produto.CEST", campo), out);
+        }
+
+        if let Some(ibscbs) = &det.imposto.IBSCBS {
+            let formato: bool = ibscbs.cClassTrib.len() == 6 && digitos(&ibscbs.cClassTrib);
+            let campo = format!("{}.imposto.IBSCBS.cClassTrib", campo);
+            checar_codigo(cclasstrib, CCLASSTRIB_INVALIDO, &ibscbs.cClassTrib, formato, &campo, out);
+        }
+    }
+
+    checar_municipio(nfe.ide.cMunFG, None, UF::from_cUF(nfe.ide.cUF), "ide.cMunFG", out);
+    let ender = &nfe.emit.enderEmit;
+    checar_municipio(ender.cMun, Some(&ender.xMun), ender.UF, "emit.enderEmit", out);
+    if let Some(ender) = nfe.dest.as_ref().and_then(|d| d.enderDest.as_ref()) {
+        checar_municipio(ender.cMun, Some(&ender.xMun), ender.UF, "dest.enderDest", out);
+    }
+    if let Some(local) = &nfe.retirada {
+        checar_municipio(local.cMun, Some(&local.xMun), local.UF, "retirada", out);
+    }
+    if let Some(local) = &nfe.entrega {
+        checar_municipio(local.cMun, Some(&local.xMun), local.UF, "entrega", out);
+    }
+}
+
+fn digitos(codigo: &str) -> bool {
+    !codigo.is_empty() && codigo.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn checar_codigo(tabela: fn() -> &'static Tabela, regra: &'static str, codigo: &str, formato: bool, campo: &str, out: &mut Vec<Diagnostico>) {
+    if !formato {
+        out.push(Diagnostico::new(regra, campo, format!("Código '{}' fora do formato", codigo)));
+    } else if tabela().contem(codigo) == Some(false) {
+        let tabela = tabela();
+        out.push(Diagnostico::new(
+            regra,
+            campo,
+            format!("Código '{}' não consta na tabela {} versão {}", codigo, tabela.nome, tabela.versao),
+        ));
+    }
+}
+
+fn checar_municipio(cMun: u32, xMun: Option<&str>, uf: Option<UF>, campo: &str, out: &mut Vec<Diagnostico>) {
+    if cMun == MUNICIPIO_EXTERIOR {
+        return;
+    }
+    let campo_cMun = if xMun.is_some() { format!("{}.cMun", campo) } else { campo.to_string() };
+
+    if !municipio_valido(cMun) {
+        out.push(Diagnostico::new(MUNICIPIO_INVALIDO, campo_cMun, format!("Código de município {} inválido", cMun)));
+        return;
+    }
+    if let Some(uf) = uf
+        && let Some(cUF) = uf.cUF()
+        && cMun / 100000 != cUF as u32
+    {
+        out.push(Diagnostico::new(
+            MUNICIPIO_UF_DIVERGENTE,
+            campo_cMun.clone(),
+            format!("Município {} não pertence à UF {:?}", cMun, uf),
+        ));
+    }
+
+    let tabela = municipios();
+    match tabela.buscar(&cMun.to_string()) {
+        Some(nome) => {
+            if let Some(xMun) = xMun
+                && normalizar_nome(xMun) != normalizar_nome(nome)
+            {
+                out.push(Diagnostico::new(
+                    MUNICIPIO_NOME_DIVERGENTE,
+                    format!("{}.xMun", campo),
+                    format!("xMun '{}' difere do nome IBGE '{}' para o código {}", xMun, nome, cMun),
+                ));
+            }
+        }
+        None if tabela.completa => out.push(Diagnostico::new(
+            MUNICIPIO_INVALIDO,
+            campo_cMun,
+            format!("Município {} não consta na tabela {} versão {}", cMun, tabela.nome, tabela.versao),
+        )),
+        None => (),
+    }
+}
+
+/// Compara nomes sem acentos, caixa e pontuação ("SAO PAULO" == "São Paulo").
+fn normalizar_nome(nome: &str) -> String {
+    nome.chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' | 'Á' | 'À' | 'Â' | 'Ã' | 'Ä' => 'A',
+            'é' | 'è' | 'ê' | 'ë' | 'É' | 'È' | 'Ê' | 'Ë' => 'E',
+            'í' | 'ì' | 'î' | 'ï' | 'Í' | 'Ì' | 'Î' | 'Ï' => 'I',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' | 'Ó' | 'Ò' | 'Ô' | 'Õ' | 'Ö' => 'O',
+            'ú' | 'ù' | 'û' | 'ü' | 'Ú' | 'Ù' | 'Û' | 'Ü' => 'U',
+            'ç' | 'Ç' => 'C',
+            c => c.to_ascii_uppercase(),
+        })
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
@@ -0,0 +1,370 @@
+#![allow(non_snake_case)]
+use rust_decimal::Decimal;
+
+use crate::{
+    nfe::{
+        codigos::TPag,
+        impostos::{cofins::{self, COFINS}, icms::Icms, ipi, pis::{self, PIS}},
+        total::ISSQNtot,
+        validacao::Diagnostico,
+    },
+    nfes::{Det, NFe, Tributacao},
+};
+
+const TOTAL_DIVERGENTE: &str = "TOTAL_DIVERGENTE";
+const VNF_DIVERGENTE: &str = "VNF_DIVERGENTE";
+const PAGAMENTO_INSUFICIENTE: &str = "PAGAMENTO_INSUFICIENTE";
+
+/// Somatório dos itens que compõem os grupos ICMSTot e ISSQNtot. Itens de serviço (ISSQN)
+/// entram apenas nos campos `Serv`. São somas de valores informados, sem arredondamento, por isso
+/// a comparação com os totais é exata.
+#[derive(Default)]
+struct Somatorio {
+    vProd: Decimal,
+    vDesc: Decimal,
+    vFrete: Decimal,
+    vSeg: Decimal,
+    vOutro: Decimal,
+    vBC: Decimal,
+    vICMS: Decimal,
+    vICMSDeson: Decimal,
+    vICMSDesonDeduzido: Decimal,
+    vBCST: Decimal,
+    vST: Decimal,
+    vFCP: Decimal,
+    vFCPST: Decimal,
+    vFCPSTRet: Decimal,
+    vII: Decimal,
+    vIPI: Decimal,
+    vIPIDevol: Decimal,
+    vPIS: Decimal,
+    vCOFINS: Decimal,
+    vPISST: Decimal,
+    vCOFINSST: Decimal,
+    vServ: Decimal,
+    vPISServ: Decimal,
+    vCOFINSServ: Decimal,
+}
+
+/// Confere os campos do ICMSTot com o somatório dos itens, a fórmula do vNF e a cobertura
+/// do pagamento. Habilitada por `PARSER_VALIDAR_TOTAIS`.
+pub fn validar_nfe(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    let s: Somatorio = somar_itens(&nfe.produtos);
+    let tot = &nfe.total.ICMSTot;
+
+    let campos = [
+        ("vProd", tot.vProd, s.vProd),
+        ("vDesc", tot.vDesc, s.vDesc),
+        ("vFrete", tot.vFrete, s.vFrete),
+        ("vSeg", tot.vSeg, s.vSeg),
+        ("vOutro", tot.vOutro, s.vOutro),
+        ("vBC", tot.vBC, s.vBC),
+        ("vICMS", tot.vICMS, s.vICMS),
+        ("vICMSDeson", tot.vICMSDeson, s.vICMSDeson),
+        ("vBCST", tot.vBCST, s.vBCST),
+        ("vST", tot.vST, s.vST),
+        ("vFCP", tot.vFCP, s.vFCP),
+        ("vFCPST", tot.vFCPST, s.vFCPST),
+        ("vFCPSTRet", tot.vFCPSTRet, s.vFCPSTRet),
+        ("vII", tot.vII, s.vII),
+        ("vIPI", tot.vIPI, s.vIPI),
+        ("vIPIDevol", tot.vIPIDevol, s.vIPIDevol),
+        ("vPIS", tot.vPIS, s.vPIS),
+        ("vCOFINS", tot.vCOFINS, s.vCOFINS),
+    ];
+    for (campo, informado, calculado) in campos {
+        if informado != calculado {
+            out.push(Diagnostico::new(
+                TOTAL_DIVERGENTE,
+                format!("total.ICMSTot.{}", campo),
+                format!("Total {} difere do somatório dos itens {}", informado, calculado),
+            ));
+        }
+    }
+
+    let vServ: Decimal = match &nfe.total.ISSQNtot {
+        Some(issqn) => {
+            validar_issqn(issqn, &s, out);
+            issqn.vServ.unwrap_or_default()
+        }
+        None => s.vServ,
+    };
+
+    // vNF = vProd - vDesc - vICMSDeson (indDeduzDeson=1) + vST + vFCPST + vFrete + vSeg + vOutro
+    //       + vII + vIPI + vIPIDevol + vServ + PIS/COFINS ST (indSoma=1)
+    // O vProd do ICMSTot não inclui os itens de serviço, que entram uma única vez pelo vServ.
+    let vNF: Decimal = tot.vProd - tot.vDesc - s.vICMSDesonDeduzido + tot.vST + tot.vFCPST + tot.vFrete + tot.vSeg
+        + tot.vOutro + tot.vII + tot.vIPI + tot.vIPIDevol + vServ + s.vPISST + s.vCOFINSST;
+    if tot.vNF != vNF {
+        out.push(Diagnostico::new(
+            VNF_DIVERGENTE,
+            "total.ICMSTot.vNF",
+            format!("vNF {} difere do valor calculado {}", tot.vNF, vNF),
+        ));
+    }
+
+    validar_pagamento(nfe, out);
+}
+
+/// Confere o ISSQNtot com o somatório dos itens de serviço.
+fn validar_issqn(issqn: &ISSQNtot, s: &Somatorio, out: &mut Vec<Diagnostico>) {
+    let campos = [
+        ("vServ", issqn.vServ, s.vServ),
+        ("vPIS", issqn.vPIS, s.vPISServ),
+        ("vCOFINS", issqn.vCOFINS, s.vCOFINSServ),
+    ];
+    for (campo, informado, calculado) in campos {
+        let informado: Decimal = informado.unwrap_or_default();
+        if informado != calculado {
+            out.push(Diagnostico::new(
+                TOTAL_DIVERGENTE,
+                format!("total.ISSQNtot.{}", campo),
+                format!("Total {} difere do somatório dos itens de serviço {}", informado, calculado),
+            ));
+        }
+    }
+}
+
+fn validar_pagamento(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    let Some(det_pag) = &nfe.pag.detPag else {
+        return;
+    };
+    if det_pag.iter().any(|p| p.tPag == TPag::SemPagamento) {
+        return;
+    }
+
+    let vPag: Decimal = det_pag.iter().map(|p| p.vPag).sum();
+    let vTroco: Decimal = nfe.pag.vTroco.unwrap_or_default();
+    let vNF: Decimal = nfe.total.ICMSTot.vNF;
+    if vPag - vTroco < vNF {
+        out.push(Diagnostico::new(
+            PAGAMENTO_INSUFICIENTE,
+            "pag.detPag",
+            format!("Pagamentos {} menos troco {} não cobrem o vNF {}", vPag, vTroco, vNF),
+        ));
+    }
+}
+
+fn somar_itens(produtos: &[Det]) -> Somatorio {
+    let mut s: Somatorio = Somatorio::default();
+    for det in produtos {
+        let prod = &det.produto;
+        let imposto = &det.imposto;
+        let servico: bool = matches!(imposto.tributacao, Some(Tributacao::Servico { .. }));
+        let vPIS: Decimal = imposto.PIS.as_ref().map(get_vPIS).unwrap_or_default();
+        let vCOFINS: Decimal = imposto.COFINS.as_ref().map(get_vCOFINS).unwrap_or_default();
+        if servico {
+            if prod.indTot {
+                s.vServ += prod.vProd;
+            }
+            s.vPISServ += vPIS;
+            s.vCOFINSServ += vCOFINS;
+        } else {
+            if prod.indTot {
+                s.vProd += prod.vProd;
+            }
+            s.vPIS += vPIS;
+            s.vCOFINS += vCOFINS;
+        }
+        s.vDesc += prod.vDesc.unwrap_or_default();
+        s.vFrete += prod.vFrete.unwrap_or_default();
+        s.vSeg += prod.vSeg.unwrap_or_default();
+        s.vOutro += prod.vOutro.unwrap_or_default();
+
+        match &imposto.tributacao {
+            Some(Tributacao::Mercadoria { ICMS, IPI, II }) => {
+                somar_icms(ICMS, &mut s);
+                s.vIPI += IPI.as_ref().map(get_vIPI).unwrap_or_default();
+                s.vII += II.as_ref().map(|ii| ii.vII).unwrap_or_default();
+            }
+            Some(Tributacao::Servico { IPI, .. }) => {
+                s.vIPI += IPI.as_ref().map(get_vIPI).unwrap_or_default();
+            }
+            None => (),
+        }
+
+        if let Some(st) = &imposto.PISST
+            && st.indSomaPISST == Some(true)
+        {
+            s.vPISST += st.vPIS;
+        }
+        if let Some(st) = &imposto.COFINSST
+            && st.indSomaCOFINSST == Some(1)
+        {
+            s.vCOFINSST += st.vCOFINS;
+        }
+
+        s.vIPIDevol += det.impostoDevol.as_ref().map(|d| d.IPI.vIPIDevol).unwrap_or_default();
+    }
+    s
+}
+
+fn somar_icms(icms: &Icms, s: &mut Somatorio) {
+    s.vBC += icms.vBC.unwrap_or_default();
+    s.vICMS += icms.vICMS.unwrap_or_default();
+    s.vBCST += icms.vBCST.unwrap_or_default();
+    s.vST += icms.vICMSST.unwrap_or_default();
+    s.vFCP += icms.vFCP.unwrap_or_default();
+    s.vFCPST += icms.vFCPST.unwrap_or_default();
+    s.vFCPSTRet += icms.vFCPSTRet.unwrap_or_default();
+
+    let deson: Decimal = icms.vICMSDeson.unwrap_or_default();
+    s.vICMSDeson += deson;
+    if icms.indDeduzDeson == Some(true) {
+        s.vICMSDesonDeduzido += deson;
+    }
+}
+
+fn get_vIPI(ipi: &ipi::Ipi) -> Decimal {
+    match &ipi.Tributacao {
+        ipi::Tributacao::IPITrib(t) => t.vIPI,
+        ipi::Tributacao::IPINT { .. } => Decimal::ZERO,
+    }
+}
+
+fn get_vPIS(pis: &PIS) -> Decimal {
+    match &pis.tributacao {
+        pis::Tributacao::PISAliq(t) => t.vPIS,
+        pis::Tributacao::PISQtde(t) => t.vPIS,
+        pis::Tributacao::PISOutr(t) => t.vPIS,
+        pis::Tributacao::PISNT { .. } => Decimal::ZERO,
+    }
+}
+
+fn get_vCOFINS(cofins: &COFINS) -> Decimal {
+    match &cofins.tributacao {
+        cofins::Tributacao::COFINSAliq(t) => t.vCOFINS,
+        cofins::Tributacao::COFINSQtde(t) => t.vCOFINS,
+        cofins::Tributacao::COFINSOutr(t) => t.vCOFINS,
+        cofins::Tributacao::COFINSNT { .. } => Decimal::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nfe::{
+        det::imposto_devol::{ImpostoDevol, IpiDevol},
+        impostos::issqn::ISSQN,
+        pag::DetPag,
+    };
+
+    fn d(valor: &str) -> Decimal {
+        valor.parse().unwrap()
+    }
+
+    /// Venda de 100,00 com ICMS ST e FCP ST, IPI devolvido de 5,00 e pagamento em dinheiro do vNF.
+    fn nfe_balanceada() -> NFe {
+        let mut nfe: NFe = NFe::default();
+        let mut det: Det = Det::default();
+        det.produto.vProd = d("100.00");
+        det.produto.indTot = true;
+        let icms: Icms = Icms {
+            vBC: Some(d("100.00")),
+            vICMS: Some(d("18.00")),
+            vBCST: Some(d("140.00")),
+            vICMSST: Some(d("7.20")),
+            vFCPST: Some(d("2.80")),
+            ..Default::default()
+        };
+        det.imposto.tributacao = Some(Tributacao::Mercadoria { ICMS: icms, IPI: None, II: None });
+        det.impostoDevol = Some(ImpostoDevol { pDevol: d("100.00"), IPI: IpiDevol { vIPIDevol: d("5.00") } });
+        nfe.produtos.push(det);
+
+        let tot = &mut nfe.total.ICMSTot;
+        tot.vProd = d("100.00");
+        tot.vBC = d("100.00");
+        tot.vICMS = d("18.00");
+        tot.vBCST = d("140.00");
+        tot.vST = d("7.20");
+        tot.vFCPST = d("2.80");
+        tot.vIPIDevol = d("5.00");
+        tot.vNF = d("115.00");
+
+        nfe.pag.detPag = Some(vec![DetPag { tPag: TPag::Dinheiro, vPag: d("115.00"), ..Default::default() }]);
+        nfe
+    }
+
+    fn diagnosticos(nfe: &NFe) -> Vec<(&'static str, String)> {
+        let mut out: Vec<Diagnostico> = Vec::new();
+        validar_nfe(nfe, &mut out);
+        out.into_iter().map(|d| (d.regra, d.campo)).collect()
+    }
+
+    #[test]
+    fn aceita_nfe_balanceada() {
+        assert!(diagnosticos(&nfe_balanceada()).is_empty());
+    }
+
+    #[test]
+    fn detecta_vprod_com_um_centavo_de_diferenca() {
+        let mut nfe: NFe = nfe_balanceada();
+        nfe.total.ICMSTot.vProd = d("100.01");
+        nfe.total.ICMSTot.vNF = d("115.01");
+        nfe.pag.detPag.as_mut().unwrap()[0].vPag = d("115.01");
+        assert_eq!(diagnosticos(&nfe), vec![(TOTAL_DIVERGENTE, "total.ICMSTot.vProd".to_string())]);
+    }
+
+    #[test]
+    fn detecta_vst_com_um_centavo_de_diferenca() {
+        let mut nfe: NFe = nfe_balanceada();
+        nfe.total.ICMSTot.vST = d("7.19");
+        // O vNF informado continua o da soma dos itens, e passa a divergir da fórmula.
+        assert_eq!(
+            diagnosticos(&nfe),
+            vec![
+                (TOTAL_DIVERGENTE, "total.ICMSTot.vST".to_string()),
+                (VNF_DIVERGENTE, "total.ICMSTot.vNF".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn detecta_vfcpst_com_um_centavo_de_diferenca() {
+        let mut nfe: NFe = nfe_balanceada();
+        nfe.total.ICMSTot.vFCPST = d("2.81");
+        nfe.total.ICMSTot.vNF = d("115.01");
+        nfe.pag.detPag.as_mut().unwrap()[0].vPag = d("115.01");
+        assert_eq!(diagnosticos(&nfe), vec![(TOTAL_DIVERGENTE, "total.ICMSTot.vFCPST".to_string())]);
+    }
+
+    #[test]
+    fn detecta_vipidevol_com_um_centavo_de_diferenca() {
+        let mut nfe: NFe = nfe_balanceada();
+        nfe.total.ICMSTot.vIPIDevol = d("4.99");
+        nfe.total.ICMSTot.vNF = d("114.99");
+        assert_eq!(diagnosticos(&nfe), vec![(TOTAL_DIVERGENTE, "total.ICMSTot.vIPIDevol".to_string())]);
+    }
+
+    #[test]
+    fn item_de_servico_entra_no_vnf_pelo_vserv() {
+        let mut nfe: NFe = nfe_balanceada();
+        let mut det: Det = Det::default();
+        det.produto.vProd = d("50.00");
+        det.produto.indTot = true;
+        det.imposto.tributacao = Some(Tributacao::Servico { IPI: None, ISSQN: ISSQN::default() });
+        nfe.produtos.push(det);
+        nfe.total.ISSQNtot = Some(ISSQNtot { vServ: Some(d("50.00")), ..Default::default() });
+        nfe.total.ICMSTot.vNF = d("165.00");
+        nfe.pag.detPag.as_mut().unwrap()[0].vPag = d("165.00");
+        assert!(diagnosticos(&nfe).is_empty());
+
+        // O vProd do ICMSTot não inclui o serviço.
+        nfe.total.ICMSTot.vProd = d("150.00");
+        nfe.total.ICMSTot.vNF = d("215.00");
+        nfe.pag.detPag.as_mut().unwrap()[0].vPag = d("215.00");
+        assert_eq!(diagnosticos(&nfe), vec![(TOTAL_DIVERGENTE, "total.ICMSTot.vProd".to_string())]);
+    }
+
+    #[test]
+    fn detecta_pagamento_menor_que_vnf() {
+        let mut nfe: NFe = nfe_balanceada();
+        nfe.pag.detPag.as_mut().unwrap()[0].vPag = d("114.99");
+        assert_eq!(diagnosticos(&nfe), vec![(PAGAMENTO_INSUFICIENTE, "pag.detPag".to_string())]);
+
+        // O troco é descontado do pagamento.
+        nfe.pag.detPag.as_mut().unwrap()[0].vPag = d("120.00");
+        nfe.pag.vTroco = Some(d("5.01"));
+        assert_eq!(diagnosticos(&nfe), vec![(PAGAMENTO_INSUFICIENTE, "pag.detPag".to_string())]);
+    }
+}
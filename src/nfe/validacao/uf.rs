@@ -0,0 +1,72 @@
+#![allow(non_snake_case)]
+use crate::{
+    nfe::validacao::Diagnostico,
+    nfes::{NFe, UF},
+};
+
+const CUF_DIVERGENTE: &str = "CUF_DIVERGENTE";
+const UF_AUSENTE: &str = "UF_AUSENTE";
+
+/// Confere a UF do cUF da NF-e com a UF do emitente. Um cUF fora da tabela IBGE já é reportado
+/// no parse; a comparação com a chave de acesso é feita em `validacao::chave`.
+pub fn validar_nfe(nfe: &NFe, out: &mut Vec<Diagnostico>) {
+    let cUF: u8 = nfe.ide.cUF;
+
+    if let (Some(uf), Some(uf_emit)) = (UF::from_cUF(cUF), nfe.emit.enderEmit.UF)
+        && uf != uf_emit
+    {
+        out.push(Diagnostico::new(
+            CUF_DIVERGENTE,
+            "emit.enderEmit.UF",
+            format!("UF do emitente {:?} difere da UF {:?} do cUF {}", uf_emit, uf, cUF),
+        ));
+    }
+
+    let ufs_obrigatorias = [
+        ("emit.enderEmit.UF", Some(nfe.emit.enderEmit.UF)),
+        ("dest.enderDest.UF", nfe.dest.as_ref().and_then(|d| d.enderDest.as_ref()).map(|e| e.UF)),
+        ("retirada.UF", nfe.retirada.as_ref().map(|l| l.UF)),
+        ("entrega.UF", nfe.entrega.as_ref().map(|l| l.UF)),
+    ];
+    for (campo, uf) in ufs_obrigatorias {
+        // Grupo presente, mas sem UF (ou com <UF/> vazia). Uma UF desconhecida, descartada no
+        // parse, já tem o seu diagnóstico.
+        if let Some(None) = uf
+            && !nfe.diagnosticos.iter().any(|d| d.campo == campo)
+        {
+            out.push(Diagnostico::new(UF_AUSENTE, campo, "UF obrigatória não informada"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regras(nfe: &NFe) -> Vec<&'static str> {
+        let mut out: Vec<Diagnostico> = Vec::new();
+        validar_nfe(nfe, &mut out);
+        out.iter().map(|d| d.regra).collect()
+    }
+
+    #[test]
+    fn uf_do_emitente_ausente() {
+        let nfe: NFe = NFe::default();
+        assert_eq!(regras(&nfe), vec![UF_AUSENTE]);
+    }
+
+    #[test]
+    fn uf_desconhecida_nao_e_reportada_como_ausente() {
+        let mut nfe: NFe = NFe::default();
+        nfe.diagnosticos.push(Diagnostico::new("UF_INVALIDA", "emit.enderEmit.UF", "UF 'XX' desconhecida, descartada"));
+        assert!(regras(&nfe).is_empty());
+    }
+
+    #[test]
+    fn cuf_diverge_da_uf_do_emitente() {
+        let mut nfe: NFe = NFe::default();
+        nfe.ide.cUF = 35;
+        nfe.emit.enderEmit.UF = Some(UF::MG);
+        assert_eq!(regras(&nfe), vec![CUF_DIVERGENTE]);
+    }
+}
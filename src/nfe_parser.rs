@@ -4,14 +4,14 @@ use std::{error::Error};
 
 use bytes::Bytes;
 use quick_xml::{
-    Reader,
     events::{Event},
 };
 use rust_decimal::Decimal;
 
 
 use crate::{
-    nfe::{agropecuario::parse_agropecuario, cana::parse_cana, cobr::{Cobr, Dup, Fat}, common::{get_tag_attribute, read_text, ParseError, XmlReader}, compra::parse_compra, det::imposto_devol::{ImpostoDevol, IpiDevol}, eventos::evento::{parse_evento_nfe, EventoJson}, exporta::parse_exporta, impostos::{
+    falha::Falha,
+    nfe::{agropecuario::parse_agropecuario, cana::parse_cana, chave::ChaveAcesso, ciclo_vida::{ocorrencias_eventos, ocorrencias_nfe, Ocorrencia}, cobr::{Cobr, Dup, Fat}, common::{get_tag_attribute, read_text, ErroNoCaminho, ParseError, XmlReader}, codigos::{descricao, parse_codigo}, config::get_config, compra::parse_compra, data_hora::{parse_data, parse_data_hora}, documento::normalizar_documento, empresas::classificar, encoding::{decode_xml, XmlUtf8}, det::imposto_devol::{ImpostoDevol, IpiDevol}, eventos::evento::{parse_evento_nfe, Evento, EventoJson}, exporta::parse_exporta, impostos::{
         cibs::{
             GIBSMun, TCredPres, TDevTrib, TDif, TRed, TTribCompraGov, TTribRegular, ValorCredPres, GCBS, GIBSUF, TCIBS
         },
@@ -27,8 +27,8 @@ use crate::{
         monofasia::{GMonoDif, GMonoPadrao, GMonoRet, GMonoReten, TMonofasia},
         pis::{self, CalculoPISOutr, PISAliq, PISOutr, PISQtde, TipoPis, PIS},
         pis_st::{CalculoPisSt, PISST},
-    }, infAdic::parse_infAdic, inf_intermed::parse_infIntermed, inf_resp_tec::parse_infRespTec, pag::parse_pag, total::parse_total, transp::{Lacre, RetTransp, TVeiculo, Transp, Transporta, TransporteRodoviario, VeiculoTransporte, Vol}}, nfes::{
-        Adi, Arma, Avulsa, Cide, Combustivel, CompraGov, Dest, Det, DetExport, Emit, EmitenteId, Encerrante, EnderEmi, ExportInd, GCred, Ide, Imposto, InfProdEmb, InfProdNFF, Local, Medicamento, NFRef, NFe, NfeJson, OrigComb, Prod, ProdutoEspecifico, RefECFData, RefNFData, RefNFPData, Tributacao, Veiculo, DI, UF
+    }, infAdic::parse_infAdic, inf_intermed::parse_infIntermed, inf_resp_tec::parse_infRespTec, pag::parse_pag, prot_nfe::parse_protNFe, tabelas::{cclasstrib, cest, cfop, descricao as descricao_tabela, municipios, ncm}, total::parse_total, validacao, transp::{Lacre, RetTransp, TVeiculo, Transp, Transporta, TransporteRodoviario, VeiculoTransporte, Vol}}, nfes::{
+        Adi, Arma, Avulsa, Cide, Combustivel, CompraGov, Dest, Det, DetExport, Emit, EmitenteId, Encerrante, EnderEmi, ExportInd, GCred, Ide, Imposto, InfProdEmb, InfProdNFF, Local, Medicamento, NFRef, NFe, NfeJson, OrigComb, Prod, ProdutoEspecifico, RefECFData, RefNFData, RefNFPData, Tributacao, Veiculo, DI, UF, parse_cUF, parse_uf
     }
 };
 
@@ -53,16 +53,90 @@ enum Modelo {
 
 
 
-pub fn parse_xml(xml: Bytes, company_id: i64, org_id: i64) -> Result<Vec<u8>, Box<dyn Error>> {
+/// JSON a ser publicado e as ocorrências de ciclo de vida (autorização, eventos) do documento.
+pub struct ResultadoParse {
+    pub json: Vec<u8>,
+    pub ocorrencias: Vec<Ocorrencia>,
+    pub identificacao: Identificacao,
+}
+
+/// Identificação do documento, usada nas propriedades e no roteamento da mensagem publicada.
+/// Em lotes, modelo, ambiente e UF vêm do primeiro documento.
+#[derive(Debug, Clone, Default)]
+pub struct Identificacao {
+    /// Tipo do documento: `nfe`, `nfce`, `evento` ou `lote_evento`.
+    pub tipo: &'static str,
+    /// Chave de acesso da NF-e ou Id do evento. `None` em lotes com mais de um documento.
+    pub id: Option<String>,
+    /// Modelo do documento fiscal (`55`, `65`).
+    pub modelo: Option<String>,
+    /// Ambiente: `1` produção, `2` homologação.
+    pub tpAmb: Option<String>,
+    /// Sigla da UF do emitente ou do órgão do evento (`AN` para o Ambiente Nacional).
+    pub uf: Option<String>,
+}
+
+pub fn parse_xml(xml: Bytes, company_id: i64, org_id: i64) -> Result<ResultadoParse, Falha> {
+    parse_documento(xml, company_id, org_id).map_err(classificar_erro)
+}
+
+/// Modelos não suportados vão para revisão manual; valores rejeitados pelo modo estrito e
+/// qualquer outro erro indicam um XML que nunca será processado. O caminho da falha é o
+/// elemento em que o leitor parou, quando conhecido.
+fn classificar_erro(e: Box<dyn Error>) -> Falha {
+    let (e, caminho) = match e.downcast::<ErroNoCaminho>() {
+        Ok(erro) => (erro.erro, Some(erro.caminho).filter(|c| !c.is_empty())),
+        Err(e) => (e, None),
+    };
+    let falha: Falha = match e.downcast_ref::<ParseError>() {
+        Some(ParseError::ModeloDesconhecido) => Falha::revisao_manual("unsupported_model", &e),
+        Some(ParseError::ValorInvalido(campo, _)) => Falha::permanente("invalid_value", &e).com_caminho(campo),
+        Some(ParseError::CampoDesconhecido(campo) | ParseError::UnexpectedEof(campo)) => Falha::permanente("invalid_xml", &e).com_caminho(campo),
+        _ => Falha::permanente("invalid_xml", e),
+    };
+    match caminho {
+        Some(caminho) => falha.com_caminho(&caminho),
+        None => falha,
+    }
+}
+
+fn parse_documento(xml: Bytes, company_id: i64, org_id: i64) -> Result<ResultadoParse, Box<dyn Error>> {
+    let XmlUtf8 { xml, encoding, diagnostico } = decode_xml(xml)?;
     let tipo_xml: TipoXml = get_tipo_xml(&xml)?;
 
     log::debug!("Tipo XML: {:?}", tipo_xml);
     match tipo_xml {
         TipoXml::NFe(modelo) => {
+            let tipo: &'static str = match modelo {
+                Modelo::Mod65 => "nfce",
+                _ => "nfe",
+            };
             let mut nfe_json: NfeJson = parse_nfe(xml, modelo)?;
             nfe_json.company_id = company_id;
             nfe_json.org_id = org_id;
-            return Ok(serde_json::to_vec(&nfe_json)?);
+            nfe_json.encoding = encoding;
+            let documentos: Option<Vec<String>> = get_documentos_empresa(company_id);
+            let mut ocorrencias: Vec<Ocorrencia> = Vec::new();
+            if let Some(nfe) = nfe_json.nfes.first_mut() {
+                nfe.diagnosticos.splice(0..0, diagnostico);
+            }
+            for nfe in nfe_json.nfes.iter_mut() {
+                let diagnosticos: Vec<validacao::Diagnostico> = validacao::validar_nfe(nfe);
+                nfe.diagnosticos.extend(diagnosticos);
+                nfe.classificacao = documentos.as_deref().map(|d| classificar(nfe, d));
+                ocorrencias.extend(ocorrencias_nfe(nfe));
+            }
+            let id: Option<String> = match nfe_json.nfes.as_slice() {
+                [nfe] => get_chave_nfe(nfe),
+                _ => None,
+            };
+            let mut identificacao: Identificacao = Identificacao { tipo, id, ..Default::default() };
+            if let Some(nfe) = nfe_json.nfes.first() {
+                identificacao.modelo = Some(nfe.ide.r#mod.to_string()).filter(|_| nfe.ide.r#mod != 0);
+                identificacao.tpAmb = Some(nfe.ide.tpAmb.to_string()).filter(|_| nfe.ide.tpAmb != 0);
+                identificacao.uf = UF::from_cUF(nfe.ide.cUF).map(|uf| format!("{:?}", uf));
+            }
+            return Ok(ResultadoParse { json: serde_json::to_vec(&nfe_json)?, ocorrencias, identificacao });
         }
 
         TipoXml::CTe(_) => return Err(ParseError::ModeloDesconhecido.into()),
@@ -71,17 +145,82 @@ pub fn parse_xml(xml: Bytes, company_id: i64, org_id: i64) -> Result<Vec<u8>, Bo
         TipoXml::LoteCTe => return Err(ParseError::ModeloDesconhecido.into()),
 
         TipoXml::Evento | TipoXml::LoteEvento => {
+            let tipo: &'static str = match tipo_xml {
+                TipoXml::LoteEvento => "lote_evento",
+                _ => "evento",
+            };
             let mut evento: EventoJson = parse_evento_nfe(xml)?;
             evento.company_id = company_id;
             evento.org_id = org_id;
-            return Ok(serde_json::to_vec(&evento)?);
+            evento.encoding = encoding;
+            evento.diagnosticos.splice(0..0, diagnostico);
+            let diagnosticos: Vec<validacao::Diagnostico> = validacao::validar_eventos(&evento);
+            evento.diagnosticos.extend(diagnosticos);
+            let ocorrencias: Vec<Ocorrencia> = ocorrencias_eventos(&evento);
+            let id: Option<String> = match evento.eventos.as_slice() {
+                [evento] => get_id_evento(evento),
+                _ => None,
+            };
+            let mut identificacao: Identificacao = Identificacao { tipo, id, ..Default::default() };
+            if let Some(evento) = evento.eventos.first() {
+                identificacao_evento(&mut identificacao, evento);
+            }
+            return Ok(ResultadoParse { json: serde_json::to_vec(&evento)?, ocorrencias, identificacao });
         }
         TipoXml::Desconhecido => return Err(ParseError::ModeloDesconhecido.into()),
     };
 }
 
+fn get_chave_nfe(nfe: &NFe) -> Option<String> {
+    match &nfe.chave {
+        Some(chave) => Some(chave.chave.clone()),
+        None => Some(nfe.Id.trim_start_matches("NFe").to_string()).filter(|id| !id.is_empty()),
+    }
+}
+
+/// Id do evento sem o prefixo `ID` (tpEvento + chave + nSeqEvento). O retorno de evento não
+/// possui o atributo e tem o Id montado a partir dos seus campos.
+fn get_id_evento(evento: &Evento) -> Option<String> {
+    let id: String = match evento {
+        Evento::evento(evento) => evento.Id.trim_start_matches("ID").to_string(),
+        Evento::procEventoNFe(proc) => proc.evento.Id.trim_start_matches("ID").to_string(),
+        Evento::retEnvEvento(ret) => format!(
+            "{}{}{:0>2}",
+            ret.tpEvento.as_deref()?,
+            ret.chNFe.as_deref()?,
+            ret.nSeqEvento.as_deref().unwrap_or("1")
+        ),
+    };
+    Some(id).filter(|id| !id.is_empty())
+}
+
+/// Modelo (da chave), ambiente e UF do órgão do evento.
+fn identificacao_evento(identificacao: &mut Identificacao, evento: &Evento) {
+    let (chNFe, tpAmb, cOrgao): (&str, &str, &str) = match evento {
+        Evento::evento(evento) => (&evento.infEvento.chNFe, &evento.infEvento.tpAmb, &evento.infEvento.cOrgao),
+        Evento::procEventoNFe(proc) => (&proc.evento.infEvento.chNFe, &proc.evento.infEvento.tpAmb, &proc.evento.infEvento.cOrgao),
+        Evento::retEnvEvento(ret) => (ret.chNFe.as_deref().unwrap_or_default(), &ret.tpAmb, &ret.cOrgao),
+    };
+    identificacao.modelo = chNFe.get(20..22).map(str::to_string);
+    identificacao.tpAmb = Some(tpAmb.to_string()).filter(|t| !t.is_empty());
+    identificacao.uf = match cOrgao {
+        "91" => Some("AN".to_string()),
+        c => c.parse().ok().and_then(UF::from_cUF).map(|uf| format!("{:?}", uf)),
+    };
+}
+
+/// Documentos da empresa no cadastro configurado. `None` sem cadastro ou para empresa não cadastrada.
+fn get_documentos_empresa(company_id: i64) -> Option<Vec<String>> {
+    let documentos: Vec<String> = get_config().empresas.as_ref()?.documentos(company_id);
+    if documentos.is_empty() {
+        log::warn!("company_id {} não encontrado no cadastro de empresas", company_id);
+        return None;
+    }
+    Some(documentos)
+}
+
 fn get_tipo_xml(xml: &Bytes) -> Result<TipoXml, Box<dyn Error>> {
-    let mut reader: Reader<&[u8]> = Reader::from_reader(xml);
+    let mut reader: XmlReader = XmlReader::from_reader(xml);
     reader.config_mut().trim_text(true);
 
     loop {
@@ -137,16 +276,18 @@ fn get_mod_nfe(reader: &mut XmlReader) -> Result<Modelo, Box<dyn Error>> {
 
 fn parse_nfe(xml: Bytes, modelo: Modelo) -> Result<NfeJson, Box<dyn Error>> {
     let mut nfe_json: NfeJson = NfeJson::default();
-    let mut reader: Reader<&[u8]> = Reader::from_reader(&xml);
+    let mut reader: XmlReader = XmlReader::from_reader(&xml);
 
     match modelo {
         Modelo::Mod55 => {
-            let nfe: NFe = parse_NFe(&mut reader)?;
+            let mut nfe: NFe = parse_NFe(&mut reader).map_err(|e| reader.erro_no_caminho(e))?;
+            nfe.diagnosticos = reader.coletar_diagnosticos();
             nfe_json.nfes.push(nfe);
             return Ok(nfe_json);
         }
         Modelo::Mod65 => {
-            let nfe: NFe = parse_NFe(&mut reader)?;
+            let mut nfe: NFe = parse_NFe(&mut reader).map_err(|e| reader.erro_no_caminho(e))?;
+            nfe.diagnosticos = reader.coletar_diagnosticos();
             nfe_json.nfes.push(nfe);
             return Ok(nfe_json);
         }
@@ -164,14 +305,17 @@ fn parse_NFe(reader: &mut XmlReader) -> Result<NFe, Box<dyn Error>> {
     loop {
         match reader.read_event()? {
             Event::Start(e) => match e.name().as_ref() {
-                b"infNFe" => nfe.Id = get_tag_attribute(&e, b"Id")?,
+                b"infNFe" => {
+                    nfe.Id = get_tag_attribute(&e, b"Id")?;
+                    nfe.chave = ChaveAcesso::decompor(&nfe.Id);
+                }
                 b"ide" => nfe.ide = parse_ide(reader)?,
                 b"emit" => nfe.emit = parse_emit(reader)?,
                 b"avulsa" => nfe.avulsa = Some(parse_avulsa(reader)?),
                 b"dest" => nfe.dest = Some(parse_dest(reader)?),
                 b"retirada" => nfe.retirada = Some(parse_TLocal(reader, b"retirada")?),
                 b"entrega" => nfe.entrega = Some(parse_TLocal(reader, b"entrega")?),
-                //b"autXML" => (),
+                b"autXML" => nfe.autXML.get_or_insert_with(Vec::new).push(parse_autXML(reader)?),
                 b"det" => nfe.produtos.push(parse_det(reader)?),
                 b"total" => nfe.total = parse_total(reader)?,
                 b"transp" => nfe.transp = parse_transp(reader)?,
@@ -188,6 +332,7 @@ fn parse_NFe(reader: &mut XmlReader) -> Result<NFe, Box<dyn Error>> {
                 b"xSolic" => nfe.infSolicNFF = Some(read_text(reader, &e)?),
 
                 b"agropecuario" => nfe.agropecuario = Some(parse_agropecuario(reader)?),
+                b"protNFe" => nfe.protNFe = Some(parse_protNFe(reader)?),
                 _ => {}
             },
 
@@ -221,31 +366,43 @@ fn parse_ide(reader: &mut XmlReader) -> Result<Ide, Box<dyn Error>> {
                 name => {
                     let txt: String = read_text(reader, &e)?;
                     match name {
-                        b"cUF" => ide.cUF = txt.parse()?,
+                        b"cUF" => ide.cUF = parse_cUF(reader, txt, "ide.cUF")?,
                         b"cNF" => ide.cNF = txt,
                         b"natOp" => ide.natOp = txt,
                         b"mod" => ide.r#mod = txt.parse::<u8>()?,
                         b"serie" => ide.serie = txt.parse::<u16>()?,
                         b"nNF" => ide.nNF = txt.parse::<u32>()?,
-                        b"dhEmi" => ide.dhEmi = txt,
-                        b"dhSaiEnt" => ide.dhSaiEnt = Some(txt),
+                        b"dhEmi" => ide.dhEmi = parse_data_hora(txt, "ide.dhEmi")?,
+                        b"dhSaiEnt" => ide.dhSaiEnt = Some(parse_data_hora(txt, "ide.dhSaiEnt")?),
                         b"tpNF" => ide.tpNF = txt == "1",
                         b"idDest" => ide.idDest = txt.parse::<u8>()?,
-                        b"cMunFG" => ide.cMunFG = txt.parse::<u32>()?,
+                        b"cMunFG" => {
+                            ide.cMunFG = txt.parse::<u32>()?;
+                            ide.cMunFGDesc = descricao_tabela(municipios, &txt);
+                        }
                         b"cMunFGIBS" => ide.cMunFGIBS = Some(txt.parse::<u32>()?),
                         b"tpImp" => ide.tpImp = txt.parse::<u8>()?,
-                        b"tpEmis" => ide.tpEmis = txt.parse::<u8>()?,
+                        b"tpEmis" => {
+                            ide.tpEmis = parse_codigo(txt, "ide.tpEmis")?;
+                            ide.tpEmisDesc = descricao(&ide.tpEmis);
+                        }
                         b"cDV" => ide.cDV = txt.parse::<u8>()?,
                         b"tpAmb" => ide.tpAmb = txt.parse::<u8>()?,
-                        b"finNFe" => ide.finNFe = txt.parse::<u8>()?,
+                        b"finNFe" => {
+                            ide.finNFe = parse_codigo(txt, "ide.finNFe")?;
+                            ide.finNFeDesc = descricao(&ide.finNFe);
+                        }
                         b"tpNFDebito" => ide.tpNFDebito = Some(txt.parse::<u8>()?),
                         b"tpNFCredito" => ide.tpNFCredito = Some(txt.parse::<u8>()?),
                         b"indFinal" => ide.indFinal = txt == "1",
-                        b"indPres" => ide.indPres = txt.parse::<u8>()?,
+                        b"indPres" => {
+                            ide.indPres = parse_codigo(txt, "ide.indPres")?;
+                            ide.indPresDesc = descricao(&ide.indPres);
+                        }
                         b"indIntermed" => ide.indIntermed = Some(txt == "1"),
                         b"procEmi" => ide.procEmi = txt.parse::<u8>()?,
                         b"verProc" => ide.verProc = txt,
-                        b"dhCont" => ide.dhCont = Some(txt),
+                        b"dhCont" => ide.dhCont = Some(parse_data_hora(txt, "ide.dhCont")?),
                         b"xJust" => ide.xJust = Some(txt),
                         _ => {
                             log::warn!(
@@ -280,15 +437,18 @@ fn parse_emit(reader: &mut XmlReader) -> Result<Emit, Box<dyn Error>> {
                 name => {
                     let txt: String = read_text(reader, &e)?;
                     match name {
-                        b"CNPJ" => emit.EmitenteId = EmitenteId::CNPJ(txt),
-                        b"CPF" => emit.EmitenteId = EmitenteId::CPF(txt),
+                        b"CNPJ" => emit.EmitenteId = EmitenteId::CNPJ(normalizar_documento(txt)),
+                        b"CPF" => emit.EmitenteId = EmitenteId::CPF(normalizar_documento(txt)),
                         b"xNome" => emit.xNome = txt,
                         b"xFant" => emit.xFant = Some(txt),
                         b"IE" => emit.IE = txt,
                         b"IEST" => emit.IEST = Some(txt),
                         b"IM" => emit.IM = Some(txt),
                         b"CNAE" => emit.CNAE = Some(txt),
-                        b"CRT" => emit.CRT = txt.parse::<u8>()?,
+                        b"CRT" => {
+                            emit.CRT = parse_codigo(txt, "emit.CRT")?;
+                            emit.CRTDesc = descricao(&emit.CRT);
+                        }
 
                         _ => {}
                     }
@@ -313,12 +473,12 @@ fn parse_avulsa(reader: &mut XmlReader) -> Result<Avulsa, Box<dyn Error>> {
             Event::Start(e) => {
                 let txt = read_text(reader, &e)?;
                 match e.name().as_ref() {
-                    b"CNPJ" => avulsa.CNPJ = txt,
+                    b"CNPJ" => avulsa.CNPJ = normalizar_documento(txt),
                     b"xOrgao" => avulsa.xOrgao = txt,
                     b"matr" => avulsa.matr = txt,
                     b"xAgente" => avulsa.xAgente = txt,
                     b"fone" => avulsa.fone = Some(txt),
-                    b"UF" => avulsa.UF = UF::from(txt.as_str()),
+                    b"UF" => avulsa.UF = parse_uf(reader, txt, "avulsa.UF")?,
                     b"nDAR" => avulsa.nDAR = Some(txt),
                     b"dEmi" => avulsa.dEmi = Some(txt),
                     b"vDAR" => avulsa.vDAR = Some(txt.parse()?),
@@ -351,13 +511,16 @@ fn parse_dest(reader: &mut XmlReader) -> Result<Dest, Box<dyn Error>> {
                     let txt = read_text(reader, &e)?;
                     match name {
                         // Tratamento da <choice> de identificação
-                        b"CNPJ" => dest.EmitenteId = EmitenteId::CNPJ(txt),
-                        b"CPF" => dest.EmitenteId = EmitenteId::CPF(txt),
+                        b"CNPJ" => dest.EmitenteId = EmitenteId::CNPJ(normalizar_documento(txt)),
+                        b"CPF" => dest.EmitenteId = EmitenteId::CPF(normalizar_documento(txt)),
                         b"idEstrangeiro" => dest.EmitenteId = EmitenteId::idEstrangeiro(txt),
 
                         // Outros campos
                         b"xNome" => dest.xNome = Some(txt),
-                        b"indIEDest" => dest.indIEDest = txt.parse::<u8>()?,
+                        b"indIEDest" => {
+                            dest.indIEDest = parse_codigo(txt, "dest.indIEDest")?;
+                            dest.indIEDestDesc = descricao(&dest.indIEDest);
+                        }
                         b"IE" => dest.IE = Some(txt),
                         b"ISUF" => dest.ISUF = Some(txt),
                         b"IM" => dest.IM = Some(txt),
@@ -421,7 +584,10 @@ fn parse_transp(reader: &mut XmlReader) -> Result<Transp, Box<dyn Error>> {
                 name => {
                     let txt = read_text(reader, &e)?;
                     match name {
-                        b"modFrete" => transp.modFrete = txt.parse::<Decimal>()?,
+                        b"modFrete" => {
+                            transp.modFrete = parse_codigo(txt, "transp.modFrete")?;
+                            transp.modFreteDesc = descricao(&transp.modFrete);
+                        }
                         b"vagao" => transp.veiculo = Some(VeiculoTransporte::Vagao{vagao: txt}),
                         b"balsa" => transp.veiculo = Some(VeiculoTransporte::Balsa{balsa: txt}),
                         _ => (),
@@ -466,7 +632,7 @@ fn parse_TVeiculo(reader: &mut XmlReader, end_tag: &[u8]) -> Result<TVeiculo, Bo
                 let txt = read_text(reader, &e)?;
                 match e.name().as_ref() {
                     b"placa" => veiculo.placa = txt,
-                    b"UF" => veiculo.UF = Some(UF::from(txt.as_str())),
+                    b"UF" => veiculo.UF = parse_uf(reader, txt, &format!("transp.{}.UF", String::from_utf8_lossy(end_tag)))?,
                     b"RNTC" => veiculo.RNTC = Some(txt),
                     _ => (),
                 }
@@ -515,13 +681,13 @@ fn parse_transporta(reader: &mut XmlReader) -> Result<Transporta, Box<dyn Error>
             Event::Start(e) => {
                 let txt = read_text(reader, &e)?;
                 match e.name().as_ref() {
-                    b"CNPJ" => t.identificacao = Some(EmitenteId::CNPJ(txt)),
-                    b"CPF" => t.identificacao = Some(EmitenteId::CPF(txt)),
+                    b"CNPJ" => t.identificacao = Some(EmitenteId::CNPJ(normalizar_documento(txt))),
+                    b"CPF" => t.identificacao = Some(EmitenteId::CPF(normalizar_documento(txt))),
                     b"xNome" => t.xNome = Some(txt),
                     b"IE" => t.IE = Some(txt),
                     b"xEnder" => t.xEnder = Some(txt),
                     b"xMun" => t.xMun = Some(txt),
-                    b"UF" => t.UF = Some(UF::from(txt.as_str())),
+                    b"UF" => t.UF = parse_uf(reader, txt, "transp.transporta.UF")?,
                     _ => (),
                 }
             }
@@ -601,7 +767,7 @@ fn parse_dup(reader: &mut XmlReader) -> Result<Dup, Box<dyn Error>> {
                 let txt: String = read_text(reader, &e)?;
                 match e.name().as_ref() {
                     b"nDup" => dup.nDup = Some(txt),
-                    b"dVenc" => dup.dVenc = Some(txt),
+                    b"dVenc" => dup.dVenc = Some(parse_data(txt, "cobr.dup.dVenc")?),
                     b"vDup" => dup.vDup = txt.parse()?,
                     _ => (),
                 }
@@ -652,14 +818,23 @@ fn parse_prod(reader: &mut XmlReader) -> Result<Prod, Box<dyn Error>> {
                         b"cEAN" => prod.cEAN = txt,
                         b"cBarra" => prod.cBarra = Some(txt),
                         b"xProd" => prod.xProd = txt,
-                        b"NCM" => prod.NCM = txt,
+                        b"NCM" => {
+                            prod.NCMDesc = descricao_tabela(ncm, &txt);
+                            prod.NCM = txt;
+                        }
                         b"NVE" => prod.NVE.get_or_insert_with(Vec::new).push(txt),
-                        b"CEST" => prod.CEST = Some(txt),
+                        b"CEST" => {
+                            prod.CESTDesc = descricao_tabela(cest, &txt);
+                            prod.CEST = Some(txt);
+                        }
                         b"indEscala" => prod.indEscala = Some(txt),
-                        b"CNPJFab" => prod.CNPJFab = Some(txt),
+                        b"CNPJFab" => prod.CNPJFab = Some(normalizar_documento(txt)),
                         b"cBenef" => prod.cBenef = Some(txt),
                         b"EXTIPI" => prod.EXTIPI = Some(txt),
-                        b"CFOP" => prod.CFOP = txt,
+                        b"CFOP" => {
+                            prod.CFOPDesc = descricao_tabela(cfop, &txt);
+                            prod.CFOP = txt;
+                        }
                         b"uCom" => prod.uCom = txt,
                         b"qCom" => prod.qCom = txt.parse::<Decimal>()?,
                         b"vUnCom" => prod.vUnCom = txt.parse::<Decimal>()?,
@@ -734,14 +909,14 @@ fn parse_DI(reader: &mut XmlReader) -> Result<DI, Box<dyn Error>> {
                         b"nDI" => DI.nDI = txt,
                         b"dDI" => DI.dDI = txt,
                         b"xLocDesemb" => DI.xLocDesemb = txt,
-                        b"UFDesemb" => DI.UFDesemb = UF::from(txt.as_str()),
+                        b"UFDesemb" => DI.UFDesemb = parse_uf(reader, txt, "det.prod.DI.UFDesemb")?,
                         b"dDesemb" => DI.dDesemb = txt,
                         b"tpViaTransp" => DI.tpViaTransp = txt.parse::<u8>()?,
                         b"vAFRMM" => DI.vAFRMM = Some(txt.parse::<Decimal>()?),
                         b"tpIntermedio" => DI.tpIntermedio = txt.parse::<u8>()?,
-                        b"CNPJ" => DI.EmitenteId = EmitenteId::CNPJ(txt),
-                        b"CPF" => DI.EmitenteId = EmitenteId::CPF(txt),
-                        b"UFTerceiro" => DI.UFTerceiro = Some(UF::from(txt.as_str())),
+                        b"CNPJ" => DI.EmitenteId = EmitenteId::CNPJ(normalizar_documento(txt)),
+                        b"CPF" => DI.EmitenteId = EmitenteId::CPF(normalizar_documento(txt)),
+                        b"UFTerceiro" => DI.UFTerceiro = parse_uf(reader, txt, "det.prod.DI.UFTerceiro")?,
                         b"cExportador" => DI.cExportador = Some(txt),
                         _ => {}
                     }
@@ -985,7 +1160,7 @@ fn parse_comb(reader: &mut XmlReader) -> Result<Combustivel, Box<dyn Error>> {
                         b"vPart" => combustivel.vPart = Some(txt.parse()?),
                         b"CODIF" => combustivel.CODIF = Some(txt),
                         b"qTemp" => combustivel.qTemp = Some(txt.parse()?),
-                        b"UFCons" => combustivel.UFCons = UF::from(txt.as_str()),
+                        b"UFCons" => combustivel.UFCons = parse_uf(reader, txt, "det.prod.comb.UFCons")?,
                         b"pBio" => combustivel.pBio = Some(txt.parse()?),
                         _ => (),
                     }
@@ -1224,7 +1399,11 @@ fn parse_ICMS(reader: &mut XmlReader) -> Result<Icms, Box<dyn Error>> {
                         b"CSOSN" => ICMS.CSOSN = Some(txt),
 
                         // --- CÁLCULO ICMS NORMAL ---
-                        b"modBC" => ICMS.modBC = Some(txt),
+                        b"modBC" => {
+                            let codigo = parse_codigo(txt, "ICMS.modBC")?;
+                            ICMS.modBCDesc = descricao(&codigo);
+                            ICMS.modBC = Some(codigo);
+                        }
                         b"vBC" => ICMS.vBC = Some(txt.parse()?),
                         b"pRedBC" => ICMS.pRedBC = Some(txt.parse()?),
                         b"pICMS" => ICMS.pICMS = Some(txt.parse()?),
@@ -1237,7 +1416,11 @@ fn parse_ICMS(reader: &mut XmlReader) -> Result<Icms, Box<dyn Error>> {
                         b"vFCP" => ICMS.vFCP = Some(txt.parse()?),
 
                         // --- ICMS ST (CÁLCULO NA OPERAÇÃO) ---
-                        b"modBCST" => ICMS.modBCST = Some(txt),
+                        b"modBCST" => {
+                            let codigo = parse_codigo(txt, "ICMS.modBCST")?;
+                            ICMS.modBCSTDesc = descricao(&codigo);
+                            ICMS.modBCST = Some(codigo);
+                        }
                         b"pMVAST" => ICMS.pMVAST = Some(txt.parse()?),
                         b"pRedBCST" => ICMS.pRedBCST = Some(txt.parse()?),
                         b"vBCST" => ICMS.vBCST = Some(txt.parse()?),
@@ -1262,12 +1445,20 @@ fn parse_ICMS(reader: &mut XmlReader) -> Result<Icms, Box<dyn Error>> {
 
                         // --- ICMS DESONERADO ---
                         b"vICMSDeson" => ICMS.vICMSDeson = Some(txt.parse()?),
-                        b"motDesICMS" => ICMS.motDesICMS = Some(txt),
+                        b"motDesICMS" => {
+                            let codigo = parse_codigo(txt, "ICMS.motDesICMS")?;
+                            ICMS.motDesICMSDesc = descricao(&codigo);
+                            ICMS.motDesICMS = Some(codigo);
+                        }
                         b"indDeduzDeson" => ICMS.indDeduzDeson = Some(txt == "1"),
 
                         // --- ICMS ST DESONERADO ---
                         b"vICMSSTDeson" => ICMS.vICMSSTDeson = Some(txt.parse()?),
-                        b"motDesICMSST" => ICMS.motDesICMSST = Some(txt),
+                        b"motDesICMSST" => {
+                            let codigo = parse_codigo(txt, "ICMS.motDesICMSST")?;
+                            ICMS.motDesICMSSTDesc = descricao(&codigo);
+                            ICMS.motDesICMSST = Some(codigo);
+                        }
 
                         // --- ICMS EFETIVO ---
                         b"pRedBCEfet" => ICMS.pRedBCEfet = Some(txt.parse()?),
@@ -1302,7 +1493,7 @@ fn parse_ICMS(reader: &mut XmlReader) -> Result<Icms, Box<dyn Error>> {
 
                         // --- ICMS PARTILHA ---
                         b"pBCOp" => ICMS.pBCOp = Some(txt.parse()?),
-                        b"UFST" => ICMS.UFST = Some(UF::from(txt.as_str())),
+                        b"UFST" => ICMS.UFST = parse_uf(reader, txt, "det.imposto.ICMS.UFST")?,
 
                         // --- ICMS ST (REPASSE) ---
                         b"vBCSTDest" => ICMS.vBCSTDest = Some(txt.parse()?),
@@ -1338,7 +1529,7 @@ fn parse_IPI(reader: &mut XmlReader) -> Result<Ipi, Box<dyn Error>> {
                 name => {
                     let txt: String = read_text(reader, &e)?;
                     match name {
-                        b"CNPJProd" => ipi.CNPJProd = Some(txt),
+                        b"CNPJProd" => ipi.CNPJProd = Some(normalizar_documento(txt)),
                         b"cSelo" => ipi.cSelo = Some(txt),
                         b"qSelo" => ipi.qSelo = Some(txt),
                         b"cEnq" => ipi.cEnq = txt,
@@ -1748,7 +1939,10 @@ fn parse_IBSCBS(reader: &mut XmlReader) -> Result<IBSCBS, Box<dyn Error>> {
                         let txt = read_text(reader, &e)?;
                         match name {
                             b"CST" => ibscbs.CST = txt,
-                            b"cClassTrib" => ibscbs.cClassTrib = txt,
+                            b"cClassTrib" => {
+                                ibscbs.cClassTribDesc = descricao_tabela(cclasstrib, &txt);
+                                ibscbs.cClassTrib = txt;
+                            }
                             tag => {
                                 let tag_name = String::from_utf8_lossy(tag).to_string();
                                 log::warn!("Elemento IBSCBS não mapeado: {}", tag_name);
@@ -2476,6 +2670,8 @@ fn parse_IPITrib(reader: &mut XmlReader) -> Result<IPITrib, Box<dyn Error>> {
 
 fn parse_enderEmit(reader: &mut XmlReader, end_tag: &[u8]) -> Result<EnderEmi, Box<dyn Error>> {
     let mut enderEmi: EnderEmi = EnderEmi::default();
+    let grupo: &str = if end_tag == b"enderEmit" { "emit" } else { "dest" };
+    let campo_uf: String = format!("{}.{}.UF", grupo, String::from_utf8_lossy(end_tag));
     loop {
         match reader.read_event()? {
             Event::Start(e) => {
@@ -2487,9 +2683,12 @@ fn parse_enderEmit(reader: &mut XmlReader, end_tag: &[u8]) -> Result<EnderEmi, B
                     b"nro" => enderEmi.nro = txt,
                     b"xCpl" => enderEmi.xCpl = Some(txt),
                     b"xBairro" => enderEmi.xBairro = txt,
-                    b"cMun" => enderEmi.cMun = txt.parse::<u32>()?,
+                    b"cMun" => {
+                        enderEmi.cMun = txt.parse::<u32>()?;
+                        enderEmi.cMunDesc = descricao_tabela(municipios, &txt);
+                    }
                     b"xMun" => enderEmi.xMun = txt,
-                    b"UF" => enderEmi.UF = UF::from(txt.as_str()),
+                    b"UF" => enderEmi.UF = parse_uf(reader, txt, &campo_uf)?,
                     b"CEP" => enderEmi.CEP = Some(txt),
                     b"cPais" => enderEmi.cPais = Some(txt),
                     b"xPais" => enderEmi.xPais = Some(txt),
@@ -2516,8 +2715,8 @@ fn parse_TLocal(reader: &mut XmlReader, end_tag: &[u8]) -> Result<Local, Box<dyn
                 let txt = read_text(reader, &e)?;
                 match e.name().as_ref() {
                     // Tratamento da <choice> de identificação
-                    b"CNPJ" => local.EmitenteId = EmitenteId::CNPJ(txt),
-                    b"CPF" => local.EmitenteId = EmitenteId::CPF(txt),
+                    b"CNPJ" => local.EmitenteId = EmitenteId::CNPJ(normalizar_documento(txt)),
+                    b"CPF" => local.EmitenteId = EmitenteId::CPF(normalizar_documento(txt)),
 
 
                     // Outros campos
@@ -2526,9 +2725,12 @@ fn parse_TLocal(reader: &mut XmlReader, end_tag: &[u8]) -> Result<Local, Box<dyn
                     b"nro" => local.nro = txt,
                     b"xCpl" => local.xCpl = Some(txt),
                     b"xBairro" => local.xBairro = txt,
-                    b"cMun" => local.cMun = txt.parse()?,
+                    b"cMun" => {
+                        local.cMun = txt.parse()?;
+                        local.cMunDesc = descricao_tabela(municipios, &txt);
+                    }
                     b"xMun" => local.xMun = txt,
-                    b"UF" => local.UF = UF::from(txt.as_str()),
+                    b"UF" => local.UF = parse_uf(reader, txt, &format!("{}.UF", String::from_utf8_lossy(end_tag)))?,
                     b"CEP" => local.CEP = Some(txt),
                     b"cPais" => local.cPais = Some(txt),
                     b"xPais" => local.xPais = Some(txt),
@@ -2546,6 +2748,25 @@ fn parse_TLocal(reader: &mut XmlReader, end_tag: &[u8]) -> Result<Local, Box<dyn
     }
 }
 
+fn parse_autXML(reader: &mut XmlReader) -> Result<EmitenteId, Box<dyn Error>> {
+    let mut id: EmitenteId = EmitenteId::default();
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                let txt = read_text(reader, &e)?;
+                match e.name().as_ref() {
+                    b"CNPJ" => id = EmitenteId::CNPJ(normalizar_documento(txt)),
+                    b"CPF" => id = EmitenteId::CPF(normalizar_documento(txt)),
+                    _ => (),
+                }
+            }
+            Event::End(e) if e.name().as_ref() == b"autXML" => return Ok(id),
+            Event::Eof => return Err(Box::new(ParseError::UnexpectedEof("autXML".to_string()))),
+            _ => (),
+        }
+    }
+}
+
 fn parse_nfref(reader: &mut XmlReader) -> Result<NFRef, Box<dyn Error>> {
     loop {
         match reader.read_event()? {
@@ -2582,9 +2803,9 @@ fn parse_refNF(reader: &mut XmlReader) -> Result<NFRef, Box<dyn Error>> {
             Event::Start(e) => {
                 let txt = read_text(reader, &e)?;
                 match e.name().as_ref() {
-                    b"cUF" => refNF.cUF = txt.parse::<u8>()?,
+                    b"cUF" => refNF.cUF = parse_cUF(reader, txt, "ide.NFref.refNF.cUF")?,
                     b"AAMM" => refNF.AAMM = txt,
-                    b"CNPJ" => refNF.CNPJ = txt,
+                    b"CNPJ" => refNF.CNPJ = normalizar_documento(txt),
                     b"mod" => refNF.r#mod = txt.parse::<u8>()?,
                     b"serie" => refNF.serie = txt.parse::<u16>()?,
                     b"nNF" => refNF.nNF = txt.parse::<u32>()?,
@@ -2616,10 +2837,10 @@ fn parse_refNFP(reader: &mut XmlReader) -> Result<NFRef, Box<dyn Error>> {
             Event::Start(e) => {
                 let txt = read_text(reader, &e)?;
                 match e.name().as_ref() {
-                    b"cUF" => refNFP.cUF = txt.parse::<u8>()?,
+                    b"cUF" => refNFP.cUF = parse_cUF(reader, txt, "ide.NFref.refNFP.cUF")?,
                     b"AAMM" => refNFP.AAMM = txt,
-                    b"CNPJ" => refNFP.EmitenteId = EmitenteId::CNPJ(txt),
-                    b"CPF" => refNFP.EmitenteId = EmitenteId::CPF(txt),
+                    b"CNPJ" => refNFP.EmitenteId = EmitenteId::CNPJ(normalizar_documento(txt)),
+                    b"CPF" => refNFP.EmitenteId = EmitenteId::CPF(normalizar_documento(txt)),
                     b"IE" => refNFP.IE = txt,
                     b"mod" => refNFP.r#mod = txt.parse::<u8>()?,
                     b"serie" => refNFP.serie = txt.parse::<u16>()?,
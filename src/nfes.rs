@@ -2,7 +2,7 @@
 use rust_decimal::Decimal;
 use serde::{Serialize};
 
-use crate::{nfe::det::imposto_devol::ImpostoDevol, nfe::impostos::{cofins::COFINS, cofins_st::COFINSST, ibs_cbs::IBSCBS, icms::Icms, icms_uf_dest::ICMSUFDest, ii::Ii, ipi::Ipi, is::IS, issqn::ISSQN, pis::PIS, pis_st::PISST}, nfe::{agropecuario::Agropecuario, cana::Cana, cobr::Cobr, compra::Compra, exporta::Exporta, infAdic::InfAdic, inf_intermed::InfIntermed, inf_resp_tec::TInfRespTec, pag::Pag, total::Total, transp::Transp}};
+use crate::{nfe::det::imposto_devol::ImpostoDevol, nfe::impostos::{cofins::COFINS, cofins_st::COFINSST, ibs_cbs::IBSCBS, icms::Icms, icms_uf_dest::ICMSUFDest, ii::Ii, ipi::Ipi, is::IS, issqn::ISSQN, pis::PIS, pis_st::PISST}, nfe::{agropecuario::Agropecuario, cana::Cana, cobr::Cobr, compra::Compra, exporta::Exporta, infAdic::InfAdic, inf_intermed::InfIntermed, inf_resp_tec::TInfRespTec, pag::Pag, prot_nfe::ProtNFe, data_hora::DataHora, codigos::{Crt, FinNFe, IndIEDest, IndPres, TpEmis}, validacao::Diagnostico, config::get_config, common::{ParseError, XmlReader}, chave::ChaveAcesso, empresas::Classificacao, total::Total, transp::Transp}};
 
 
 
@@ -10,11 +10,13 @@ use crate::{nfe::det::imposto_devol::ImpostoDevol, nfe::impostos::{cofins::COFIN
 pub struct NfeJson {
     pub company_id: i64,
     pub org_id: i64, 
+    pub encoding: String,
     pub nfes: Vec<NFe>,
 }
 #[derive(Debug, Default, Serialize)]
 pub struct NFe {
     pub Id: String,
+    pub chave: Option<ChaveAcesso>,
     pub ide: Ide,
     pub emit: Emit,
     pub avulsa: Option<Avulsa>,
@@ -35,6 +37,9 @@ pub struct NFe {
     pub infRespTec: Option<TInfRespTec>,
     pub infSolicNFF: Option<String>,
     pub agropecuario: Option<Agropecuario>,
+    pub protNFe: Option<ProtNFe>,
+    pub classificacao: Option<Classificacao>,
+    pub diagnosticos: Vec<Diagnostico>,
 }
 
 #[derive(Debug, Default, Serialize)]
@@ -53,14 +58,20 @@ pub struct Prod {
     pub cBarra: Option<String>,
     pub xProd: String,
     pub NCM: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub NCMDesc: Option<&'static str>,
     pub NVE: Option<Vec<String>>,
     pub CEST: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub CESTDesc: Option<&'static str>,
     pub indEscala: Option<String>,
     pub CNPJFab: Option<String>,
     pub cBenef: Option<String>,
     pub gCred: Option<Vec<GCred>>,
     pub EXTIPI: Option<String>,
     pub CFOP: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub CFOPDesc: Option<&'static str>,
     pub uCom: String,
     pub qCom: Decimal,
     pub vUnCom: Decimal,
@@ -181,7 +192,7 @@ pub struct Combustivel {
     pub vPart: Option<String>,
     pub CODIF: Option<String>,
     pub qTemp: Option<String>,
-    pub UFCons: UF,
+    pub UFCons: Option<UF>,
     pub CIDE: Option<Cide>,
     pub encerrante: Option<Encerrante>,
     pub pBio: Option<Decimal>,
@@ -240,7 +251,7 @@ pub struct DI {
     pub nDI: String,
     pub dDI: String,
     pub xLocDesemb: String,
-    pub UFDesemb: UF,
+    pub UFDesemb: Option<UF>,
     pub dDesemb: String,
     pub tpViaTransp: u8,
     pub vAFRMM: Option<Decimal>,
@@ -300,25 +311,33 @@ pub struct Ide {
     pub r#mod: u8,
     pub serie: u16,
     pub nNF: u32,
-    pub dhEmi: String,
-    pub dhSaiEnt: Option<String>,
+    pub dhEmi: DataHora,
+    pub dhSaiEnt: Option<DataHora>,
     pub tpNF: bool,
     pub idDest: u8,
     pub cMunFG: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cMunFGDesc: Option<&'static str>,
     pub cMunFGIBS: Option<u32>,
     pub tpImp: u8,
-    pub tpEmis: u8,
+    pub tpEmis: TpEmis,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tpEmisDesc: Option<&'static str>,
     pub cDV: u8,
     pub tpAmb: u8,
-    pub finNFe: u8,
+    pub finNFe: FinNFe,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finNFeDesc: Option<&'static str>,
     pub tpNFDebito: Option<u8>,
     pub tpNFCredito: Option<u8>,
     pub indFinal: bool,
-    pub indPres: u8,
+    pub indPres: IndPres,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indPresDesc: Option<&'static str>,
     pub indIntermed: Option<bool>,
     pub procEmi: u8,
     pub verProc: String,
-    pub dhCont: Option<String>,
+    pub dhCont: Option<DataHora>,
     pub xJust: Option<String>,
     pub NFref: Option<Vec<NFRef>>,
     pub gCompraGov: Option<CompraGov>,
@@ -337,7 +356,9 @@ pub struct Emit {
     pub IEST: Option<String>,
     pub IM: Option<String>,
     pub CNAE: Option<String>,
-    pub CRT: u8,
+    pub CRT: Crt,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub CRTDesc: Option<&'static str>,
 }
 
 #[derive(Debug, Default, Serialize)]
@@ -347,7 +368,7 @@ pub struct Avulsa {
     pub matr: String,
     pub xAgente: String,
     pub fone: Option<String>,
-    pub UF: UF,
+    pub UF: Option<UF>,
     pub nDAR: Option<String>,
     pub dEmi: Option<String>,
     pub vDAR: Option<Decimal>,
@@ -362,7 +383,9 @@ pub struct Dest {
 
     pub xNome: Option<String>,
     pub enderDest: Option<EnderEmi>,
-    pub indIEDest: u8,
+    pub indIEDest: IndIEDest,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indIEDestDesc: Option<&'static str>,
     pub IE: Option<String>,
     pub ISUF: Option<String>,
     pub IM: Option<String>,
@@ -378,8 +401,10 @@ pub struct EnderEmi {
     pub xCpl: Option<String>,
     pub xBairro: String,
     pub cMun: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cMunDesc: Option<&'static str>,
     pub xMun: String,
-    pub UF: UF,
+    pub UF: Option<UF>,
     pub CEP: Option<String>,
     pub cPais: Option<String>,
     pub xPais: Option<String>,
@@ -397,8 +422,10 @@ pub struct Local {
     pub xCpl: Option<String>,
     pub xBairro: String,
     pub cMun: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cMunDesc: Option<&'static str>,
     pub xMun: String,
-    pub UF: UF,
+    pub UF: Option<UF>,
     pub CEP: Option<String>,
     pub cPais: Option<String>,
     pub xPais: Option<String>,
@@ -460,52 +487,150 @@ pub struct RefECFData {
 }
 
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 pub enum UF {
     AC, AL, AM, AP, BA, CE, DF, ES, GO, MA,
     MG, MS, MT, PA, PB, PE, PI, PR, RJ, RN,
     RO, RR, RS, SC, SE, SP, TO, EX,
 }
 
-impl Default for UF {
-    fn default() -> Self {
-        UF::MG
+impl UF {
+    /// Código IBGE da UF (cUF). `EX` (exterior) não possui código.
+    pub fn cUF(&self) -> Option<u8> {
+        match self {
+            UF::RO => Some(11),
+            UF::AC => Some(12),
+            UF::AM => Some(13),
+            UF::RR => Some(14),
+            UF::PA => Some(15),
+            UF::AP => Some(16),
+            UF::TO => Some(17),
+            UF::MA => Some(21),
+            UF::PI => Some(22),
+            UF::CE => Some(23),
+            UF::RN => Some(24),
+            UF::PB => Some(25),
+            UF::PE => Some(26),
+            UF::AL => Some(27),
+            UF::SE => Some(28),
+            UF::BA => Some(29),
+            UF::MG => Some(31),
+            UF::ES => Some(32),
+            UF::RJ => Some(33),
+            UF::SP => Some(35),
+            UF::PR => Some(41),
+            UF::SC => Some(42),
+            UF::RS => Some(43),
+            UF::MS => Some(50),
+            UF::MT => Some(51),
+            UF::GO => Some(52),
+            UF::DF => Some(53),
+            UF::EX => None,
+        }
+    }
+
+    pub fn from_cUF(cUF: u8) -> Option<UF> {
+        match cUF {
+            11 => Some(UF::RO),
+            12 => Some(UF::AC),
+            13 => Some(UF::AM),
+            14 => Some(UF::RR),
+            15 => Some(UF::PA),
+            16 => Some(UF::AP),
+            17 => Some(UF::TO),
+            21 => Some(UF::MA),
+            22 => Some(UF::PI),
+            23 => Some(UF::CE),
+            24 => Some(UF::RN),
+            25 => Some(UF::PB),
+            26 => Some(UF::PE),
+            27 => Some(UF::AL),
+            28 => Some(UF::SE),
+            29 => Some(UF::BA),
+            31 => Some(UF::MG),
+            32 => Some(UF::ES),
+            33 => Some(UF::RJ),
+            35 => Some(UF::SP),
+            41 => Some(UF::PR),
+            42 => Some(UF::SC),
+            43 => Some(UF::RS),
+            50 => Some(UF::MS),
+            51 => Some(UF::MT),
+            52 => Some(UF::GO),
+            53 => Some(UF::DF),
+            _ => None,
+        }
     }
-    
 }
 
-impl From<&str> for UF {
-    fn from(s: &str) -> Self {
-        match s {
-            "AC" => UF::AC,
-            "AL" => UF::AL,
-            "AM" => UF::AM,
-            "AP" => UF::AP,
-            "BA" => UF::BA,
-            "CE" => UF::CE,
-            "DF" => UF::DF,
-            "ES" => UF::ES,
-            "GO" => UF::GO,
-            "MA" => UF::MA,
-            "MG" => UF::MG,
-            "MS" => UF::MS,
-            "MT" => UF::MT,
-            "PA" => UF::PA,
-            "PB" => UF::PB,
-            "PE" => UF::PE,
-            "PI" => UF::PI,
-            "PR" => UF::PR,
-            "RJ" => UF::RJ,
-            "RN" => UF::RN,
-            "RO" => UF::RO,
-            "RR" => UF::RR,
-            "RS" => UF::RS,
-            "SC" => UF::SC,
-            "SE" => UF::SE,
-            "SP" => UF::SP,
-            "TO" => UF::TO,
-            "EX" => UF::EX,
-            _ => UF::default(),
+const UF_INVALIDA: &str = "UF_INVALIDA";
+const CUF_INVALIDO: &str = "CUF_INVALIDO";
+
+/// Converte a UF lida do XML. Uma UF vazia (`<UF></UF>`) equivale ao elemento ausente (`<UF/>`).
+/// Fora do modo estrito, uma UF desconhecida é descartada e reportada em `diagnosticos`.
+pub fn parse_uf(reader: &mut XmlReader, txt: String, campo: &str) -> Result<Option<UF>, ParseError> {
+    if txt.trim().is_empty() {
+        return Ok(None);
+    }
+    match UF::try_from(txt.as_str()) {
+        Ok(uf) => Ok(Some(uf)),
+        Err(_) if get_config().strict => Err(ParseError::ValorInvalido(campo.to_string(), txt)),
+        Err(_) => {
+            log::warn!("UF inválida em {}: '{}'", campo, txt);
+            reader.diagnosticar(Diagnostico::new(UF_INVALIDA, campo, format!("UF '{}' desconhecida, descartada", txt)));
+            Ok(None)
+        }
+    }
+}
+
+/// Converte o código IBGE da UF (cUF). Fora do modo estrito, um código fora da tabela é mantido,
+/// pois compõe a chave de acesso, e reportado em `diagnosticos`.
+pub fn parse_cUF(reader: &mut XmlReader, txt: String, campo: &str) -> Result<u8, Box<dyn std::error::Error>> {
+    let cUF: u8 = txt.parse()?;
+    if UF::from_cUF(cUF).is_none() {
+        if get_config().strict {
+            return Err(Box::new(ParseError::ValorInvalido(campo.to_string(), txt)));
+        }
+        log::warn!("cUF inválido em {}: '{}'", campo, txt);
+        reader.diagnosticar(Diagnostico::new(CUF_INVALIDO, campo, format!("cUF {} não corresponde a nenhuma UF", cUF)));
+    }
+    Ok(cUF)
+}
+
+impl TryFrom<&str> for UF {
+    type Error = ParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.trim() {
+            "AC" => Ok(UF::AC),
+            "AL" => Ok(UF::AL),
+            "AM" => Ok(UF::AM),
+            "AP" => Ok(UF::AP),
+            "BA" => Ok(UF::BA),
+            "CE" => Ok(UF::CE),
+            "DF" => Ok(UF::DF),
+            "ES" => Ok(UF::ES),
+            "GO" => Ok(UF::GO),
+            "MA" => Ok(UF::MA),
+            "MG" => Ok(UF::MG),
+            "MS" => Ok(UF::MS),
+            "MT" => Ok(UF::MT),
+            "PA" => Ok(UF::PA),
+            "PB" => Ok(UF::PB),
+            "PE" => Ok(UF::PE),
+            "PI" => Ok(UF::PI),
+            "PR" => Ok(UF::PR),
+            "RJ" => Ok(UF::RJ),
+            "RN" => Ok(UF::RN),
+            "RO" => Ok(UF::RO),
+            "RR" => Ok(UF::RR),
+            "RS" => Ok(UF::RS),
+            "SC" => Ok(UF::SC),
+            "SE" => Ok(UF::SE),
+            "SP" => Ok(UF::SP),
+            "TO" => Ok(UF::TO),
+            "EX" => Ok(UF::EX),
+            _ => Err(ParseError::ValorInvalido("UF".to_string(), s.to_string())),
         }
     }
 }
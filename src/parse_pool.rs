@@ -0,0 +1,36 @@
+use std::{num::NonZeroUsize, sync::OnceLock, thread};
+
+use bytes::Bytes;
+use tokio::{sync::Semaphore, task};
+
+use crate::{falha::Falha, metricas, nfe::config::get_config, nfe_parser::{parse_xml, ResultadoParse}};
+
+/// Vagas do pool de parse. O parse roda no pool de threads bloqueantes do tokio,
+/// fora das threads que atendem o RabbitMQ (heartbeats, entregas e confirmações).
+static VAGAS: OnceLock<Semaphore> = OnceLock::new();
+
+fn get_vagas() -> &'static Semaphore {
+    VAGAS.get_or_init(|| {
+        let workers: usize = match get_config().workers {
+            0 => thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1),
+            n => n,
+        };
+        log::info!("Parse pool started with {} workers", workers);
+        Semaphore::new(workers)
+    })
+}
+
+/// Faz o parse em uma thread do pool. Sem vaga livre, aguarda na fila, o que segura o
+/// consumo do canal até que um parse termine.
+pub async fn parse(xml: Bytes, company_id: i64, org_id: i64) -> Result<ResultadoParse, Falha> {
+    let espera: metricas::Medidor = metricas::entrar_fila_parse();
+    let _vaga = get_vagas().acquire().await.map_err(|e| Falha::transitoria("parse_pool_closed", e))?;
+    drop(espera);
+
+    let _ativo: metricas::Medidor = metricas::iniciar_parse();
+    match task::spawn_blocking(move || parse_xml(xml, company_id, org_id)).await {
+        Ok(resultado) => resultado,
+        // Um panic se repetiria a cada nova tentativa com o mesmo documento.
+        Err(e) => Err(Falha::permanente("parser_panic", e)),
+    }
+}
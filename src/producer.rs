@@ -13,8 +13,12 @@ use crate::rabbitmq::common::{Message, RabbitVariables};
 
 mod logger;
 mod minio_client;
+mod falha;
+mod estado;
+mod metricas;
 mod nfe;
 mod nfe_parser;
+mod parse_pool;
 mod nfes;
 mod rabbitmq;
 
@@ -28,9 +32,10 @@ async fn main() {
 
     // Inicializa um único channel
     let mut channel: Channel = rabbitmq::common::initialize_publish_channel(
-        &"xml_queue".to_string(),
-        &"xml_queue".to_string(),
-        &String::new(),
+        "xml_queue",
+        "xml_queue",
+        "",
+        variables.passive,
         &connection,
     ).await.unwrap();
 
@@ -1,13 +1,14 @@
 use amqprs::{
      callbacks,
-    channel::{Channel, QueueBindArguments, QueueDeclareArguments},
+    channel::{BasicQosArguments, Channel, ExchangeDeclareArguments, QueueBindArguments, QueueDeclareArguments},
     connection::{self, Connection}, FieldTable,
 };
 use serde::{Deserialize, Serialize};
 use core::panic;
-use std::{env, error::Error, sync::Arc};
+use std::{env, error::Error, str::FromStr, sync::Arc, time::Duration};
 use tokio::time::sleep;
 
+use crate::rabbitmq::{dead_letter::{self, DeadLetterVariables}, properties::get_result_headers, retry::{declare_retry_queues, get_retry_delays}, routing::{self, Roteamento}};
 
 #[derive(Clone)]
 pub struct RabbitVariables {
@@ -20,7 +21,33 @@ pub struct RabbitVariables {
 
     pub routing_key: String,
     pub exchange: String,
-    pub num_channels: u8,
+    /// Template de roteamento e exchanges por tipo de documento dos resultados.
+    pub routing: Roteamento,
+    pub num_channels: u16,
+    /// Mensagens não confirmadas entregues a cada canal de consumo (`basic.qos`). `0` não limita.
+    pub prefetch_count: u16,
+    /// Mensagens processadas simultaneamente em cada canal de consumo.
+    pub channel_concurrency: usize,
+
+    /// Atraso (ms) de cada nova tentativa antes da Dead Letter Queue.
+    pub retry_delays: Vec<u32>,
+    /// Fila das mensagens que precisam de revisão manual.
+    pub parking_queue: String,
+
+    pub dead_letter: DeadLetterVariables,
+    /// Topologia gerenciada externamente: não declara exchanges, filas nem bindings.
+    pub passive: bool,
+
+    /// Fila e chave de roteamento das mudanças de situação das NF-e.
+    pub status_queue: String,
+    pub status_routing_key: String,
+
+    /// Tempo máximo de espera pela confirmação do broker a cada publicação.
+    pub confirm_timeout: Duration,
+    /// Cabeçalhos publicados junto ao resultado do parse.
+    pub result_headers: Vec<String>,
+    /// Prazo para concluir as mensagens em andamento ao receber SIGTERM/SIGINT.
+    pub shutdown_timeout: Duration,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -41,35 +68,74 @@ fn get_var(var: &str) -> String {
     env::var(var).expect(&env_not_present(&var))
 }
 
-pub fn initialize_variables() -> RabbitVariables {
-    let n_channels: u8 = match get_var("RABBITMQ_NUM_CHANNELS").parse() {
-        Ok(n) => {
-            if n > 20 {
-                panic!("Number of channels cannot exceed 20!")
-            } else {n}
-        }
+/// Variável opcional com valor padrão; valores inválidos interrompem a inicialização.
+#[inline]
+pub fn get_var_or<T: FromStr>(var: &str, padrao: T) -> T
+where
+    T::Err: std::fmt::Display,
+{
+    match env::var(var) {
+        Ok(v) if !v.trim().is_empty() => match v.trim().parse() {
+            Ok(n) => n,
+            Err(e) => panic!("Invalid {}: {}", var, e),
+        },
+        _ => padrao,
+    }
+}
 
+pub fn initialize_variables() -> RabbitVariables {
+    let n_channels: u16 = match get_var("RABBITMQ_NUM_CHANNELS").parse() {
+        Ok(n) => n,
         Err(e) => panic!("Invalid number of channels: {}", e)
     };
 
+    let channel_concurrency: usize = get_var_or("RABBITMQ_CHANNEL_CONCURRENCY", 1);
+    if channel_concurrency == 0 {
+        panic!("RABBITMQ_CHANNEL_CONCURRENCY must be greater than zero");
+    }
+    let prefetch_count: u16 = get_var_or("RABBITMQ_PREFETCH_COUNT", 10);
+    if prefetch_count != 0 && (prefetch_count as usize) < channel_concurrency {
+        log::warn!("RABBITMQ_PREFETCH_COUNT ({}) is lower than RABBITMQ_CHANNEL_CONCURRENCY ({}): some processing slots will stay idle",
+            prefetch_count, channel_concurrency);
+    }
+
     let port: u16 = match get_var("RABBITMQ_PORT").parse() {
         Ok(p) => p,
         Err(e) => panic!("Invalid port: {}", e)
     };
 
+    let consume_queue: String = get_var("RABBITMQ_CONSUME_QUEUE");
+    let status_queue: String = env::var("RABBITMQ_STATUS_QUEUE").unwrap_or("document_status_queue".to_string());
+
     RabbitVariables {
         host: get_var("RABBITMQ_HOST"),
         port: port,
         user: get_var("RABBITMQ_USER"),
         pwd: get_var("RABBITMQ_PASSWORD"),
         
-        consume_queue: get_var("RABBITMQ_CONSUME_QUEUE"),        
+        consume_queue: consume_queue.clone(),
         publish_queue: get_var("RABBITMQ_PUBLISH_QUEUE"),
 
         exchange: get_var("RABBITMQ_EXCHANGE"),
         routing_key: get_var("RABBITMQ_ROUTING_KEY"),
+        routing: routing::initialize_variables(),
 
         num_channels: n_channels,
+        prefetch_count,
+        channel_concurrency,
+
+        retry_delays: get_retry_delays(),
+        parking_queue: env::var("RABBITMQ_PARKING_QUEUE").unwrap_or("parking_queue".to_string()),
+
+        dead_letter: dead_letter::initialize_variables(&consume_queue),
+        passive: matches!(env::var("RABBITMQ_PASSIVE_TOPOLOGY").map(|v| v.to_lowercase()).as_deref(), Ok("1" | "true" | "yes")),
+
+        status_queue: status_queue.clone(),
+        status_routing_key: env::var("RABBITMQ_STATUS_ROUTING_KEY").unwrap_or(status_queue),
+
+        confirm_timeout: Duration::from_millis(get_var_or("RABBITMQ_CONFIRM_TIMEOUT_MS", 10000)),
+        result_headers: get_result_headers(),
+        shutdown_timeout: Duration::from_millis(get_var_or("RABBITMQ_SHUTDOWN_TIMEOUT_MS", 30000)),
     }
 }
 
@@ -112,57 +178,68 @@ pub async fn connect_rabbitmq(rabbit_variables: &RabbitVariables) -> Arc<connect
     return Arc::new(connection);
 }
 
-pub async fn initialize_channels(queue: &String, routing_key: &String, exchange: &String, num_channels: u8, connection: &Connection) -> Result<Vec<Channel>, Box<dyn Error>>{
+pub async fn initialize_channels(variables: &RabbitVariables, connection: &Connection) -> Result<Vec<Channel>, Box<dyn Error>>{
     let mut channels: Vec<Channel> = Vec::new();
-    for _ in 0..num_channels {
-        let channel: Channel = match initialize_consumer_channel(&queue, &routing_key, &exchange, &connection).await {
+    for _ in 0..variables.num_channels {
+        let channel: Channel = match initialize_consumer_channel(variables, &connection).await {
             Ok(v) => v,
             Err(e) => return Err(e),
         };
         channels.push(channel);
     }
 
-    log::info!("Sucessfully initialized channels on queue {}", queue);
+    log::info!("Sucessfully initialized channels on queue {}", variables.consume_queue);
     Ok(channels)
 }
 
 pub async fn initialize_consumer_channel(
-    queue: &String,
-    routing_key: &String,
-    exchange: &String,
+    variables: &RabbitVariables,
     connection: &Connection,
 ) -> Result<Channel, Box<dyn Error>> {
     let channel: Channel = connection.open_channel(None).await?;
     channel.register_callback(callbacks::DefaultChannelCallback).await?;
+    channel.basic_qos(BasicQosArguments::new(0, variables.prefetch_count, false)).await?;
+
+    if variables.passive {
+        return Ok(channel);
+    }
 
-    declare_dlx_exchange(&channel).await?;
+    let queue: &String = &variables.consume_queue;
+    declare_dlx_exchange(&channel, &variables.dead_letter).await?;
+    declare_retry_queues(&channel, queue, &variables.retry_delays).await?;
 
 
     let mut table: FieldTable = FieldTable::new();
-    table.insert("x-dead-letter-exchange".try_into()?,"dead_letter_exchange".try_into()?);
-    table.insert("x-dead-letter-routing-key".try_into()?, "dead_letter_queue".try_into()?);
+    table.insert("x-dead-letter-exchange".try_into()?, variables.dead_letter.exchange.as_str().into());
+    table.insert("x-dead-letter-routing-key".try_into()?, variables.dead_letter.routing_key.as_str().into());
 
     let declare_args: QueueDeclareArguments = QueueDeclareArguments::durable_client_named(queue).arguments(table).finish();
 
     
     channel.queue_declare(declare_args).await?;
 
-    if !exchange.is_empty() {
-        channel.queue_bind(QueueBindArguments::new(queue, exchange, routing_key)).await?;
+    if !variables.exchange.is_empty() {
+        channel.queue_bind(QueueBindArguments::new(queue, &variables.exchange, &variables.routing_key)).await?;
     }
+    channel.queue_declare(QueueDeclareArguments::durable_client_named(&variables.parking_queue)).await?;
     
     Ok(channel)
 }
 
 pub async fn initialize_publish_channel(
-    queue: &String,
-    routing_key: &String,
-    exchange: &String,
+    queue: &str,
+    routing_key: &str,
+    exchange: &str,
+    passive: bool,
     connection: &Connection,
 ) -> Result<Channel, Box<dyn Error>> {
 
     let channel: Channel = connection.open_channel(None).await?;
     channel.register_callback(callbacks::DefaultChannelCallback).await?;
+
+    if passive {
+        return Ok(channel);
+    }
     
     channel.queue_declare(QueueDeclareArguments::durable_client_named(queue)).await?;
 
@@ -173,12 +250,37 @@ pub async fn initialize_publish_channel(
     Ok(channel)
 }
 
-async fn declare_dlx_exchange(channel: &Channel) -> Result<(), Box<dyn Error>> {
-    let dlq_args: QueueDeclareArguments = QueueDeclareArguments::durable_client_named("dead_letter_queue").finish();
+/// Declara as exchanges por tipo de documento (`RABBITMQ_ROUTING_EXCHANGES`). Com a topologia
+/// passiva, apenas confere que existem: uma publicação em exchange inexistente fecha o canal.
+pub async fn declare_routing_exchanges(variables: &RabbitVariables, connection: &Connection) -> Result<(), Box<dyn Error>> {
+    let mut exchanges: Vec<&String> = variables.routing.exchanges.values().collect();
+    exchanges.sort();
+    exchanges.dedup();
+    if exchanges.is_empty() {
+        return Ok(());
+    }
+
+    let channel: Channel = connection.open_channel(None).await?;
+    for exchange in exchanges {
+        let args: ExchangeDeclareArguments = ExchangeDeclareArguments {
+            passive: variables.passive,
+            durable: true,
+            ..ExchangeDeclareArguments::new(exchange, &variables.routing.exchange_type)
+        };
+        channel.exchange_declare(args).await.map_err(|e| format!("Exchange '{}': {}", exchange, e))?;
+    }
+    channel.close().await?;
+    Ok(())
+}
+
+async fn declare_dlx_exchange(channel: &Channel, dead_letter: &DeadLetterVariables) -> Result<(), Box<dyn Error>> {
+    let dlq_args: QueueDeclareArguments = QueueDeclareArguments::durable_client_named(&dead_letter.queue)
+        .arguments(dead_letter.queue_arguments.clone())
+        .finish();
 
-    channel.exchange_declare(amqprs::channel::ExchangeDeclareArguments::new("dead_letter_exchange", "direct")).await?;
+    channel.exchange_declare(amqprs::channel::ExchangeDeclareArguments::new(&dead_letter.exchange, &dead_letter.exchange_type)).await?;
     channel.queue_declare(dlq_args).await?;
-    channel.queue_bind(QueueBindArguments::new("dead_letter_queue", "dead_letter_exchange", "dead_letter_queue")).await?;
+    channel.queue_bind(QueueBindArguments::new(&dead_letter.queue, &dead_letter.exchange, &dead_letter.routing_key)).await?;
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,276 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use amqprs::{
+    callbacks::ChannelCallback,
+    channel::{BasicPublishArguments, Channel, ConfirmSelectArguments},
+    connection::Connection,
+    error::Error as AmqpError,
+    Ack, BasicProperties, Cancel, CloseChannel, FieldTable, FieldValue, Nack, Return,
+};
+use async_trait::async_trait;
+use tokio::{sync::oneshot, time::timeout};
+
+use crate::{falha::Falha, rabbitmq::common::initialize_publish_channel};
+
+/// Cabeçalho com o delivery tag da publicação, devolvido pelo broker junto do `basic.return`.
+const HEADER_PUBLICACAO: &str = "x-publish-tag";
+
+/// Canal de publicação em modo confirm. Cada publicação é `mandatory` e aguarda o ack do broker.
+///
+/// Várias publicações podem aguardar confirmação ao mesmo tempo; cada uma é identificada pelo
+/// delivery tag que o broker atribui em ordem de envio. Um `basic.return` não traz o delivery
+/// tag, por isso ele segue no cabeçalho `x-publish-tag` da mensagem.
+pub struct ConfirmChannel {
+    channel: Channel,
+    estado: Arc<Mutex<EstadoConfirmacao>>,
+    /// Serializa o envio, para que a ordem dos delivery tags seja a ordem de `sequencia`.
+    envio: tokio::sync::Mutex<()>,
+    timeout: Duration,
+}
+
+enum Confirmacao {
+    Ack,
+    Nack,
+    Devolvida(String),
+}
+
+struct Pendente {
+    tx: oneshot::Sender<Confirmacao>,
+    devolvida: Option<String>,
+}
+
+#[derive(Default)]
+struct EstadoConfirmacao {
+    /// Delivery tag da última publicação aceita pelo canal (o broker numera a partir de 1 após o `confirm.select`).
+    sequencia: u64,
+    pendentes: BTreeMap<u64, Pendente>,
+}
+
+impl EstadoConfirmacao {
+    fn resolver(&mut self, delivery_tag: u64, multiple: bool, nack: bool) {
+        let tags: Vec<u64> = match multiple {
+            true => self.pendentes.range(..=delivery_tag).map(|(tag, _)| *tag).collect(),
+            false => vec![delivery_tag],
+        };
+        for tag in tags {
+            let Some(pendente) = self.pendentes.remove(&tag) else {
+                continue;
+            };
+            let confirmacao: Confirmacao = match (nack, pendente.devolvida) {
+                (true, _) => Confirmacao::Nack,
+                (false, Some(motivo)) => Confirmacao::Devolvida(motivo),
+                (false, None) => Confirmacao::Ack,
+            };
+            pendente.tx.send(confirmacao).ok();
+        }
+    }
+
+    /// O broker envia o `basic.return` antes do ack da mesma publicação.
+    fn devolver(&mut self, delivery_tag: u64, motivo: String) {
+        if let Some(pendente) = self.pendentes.get_mut(&delivery_tag) {
+            pendente.devolvida = Some(motivo);
+        }
+    }
+}
+
+fn com_delivery_tag(mut properties: BasicProperties, tag: u64) -> BasicProperties {
+    let mut headers: FieldTable = properties.headers().cloned().unwrap_or_default();
+    headers.insert(HEADER_PUBLICACAO.try_into().unwrap(), FieldValue::l(tag as i64));
+    properties.with_headers(headers);
+    properties
+}
+
+fn get_delivery_tag(properties: &BasicProperties) -> Option<u64> {
+    match properties.headers()?.get(&HEADER_PUBLICACAO.try_into().ok()?)? {
+        FieldValue::l(tag) => u64::try_from(*tag).ok(),
+        _ => None,
+    }
+}
+
+impl ConfirmChannel {
+    pub async fn new(channel: Channel, timeout: Duration) -> Result<ConfirmChannel, AmqpError> {
+        let estado: Arc<Mutex<EstadoConfirmacao>> = Arc::new(Mutex::new(EstadoConfirmacao::default()));
+        channel.register_callback(ConfirmCallback { estado: estado.clone() }).await?;
+        channel.confirm_select(ConfirmSelectArguments::default()).await?;
+
+        Ok(ConfirmChannel { channel, estado, envio: tokio::sync::Mutex::new(()), timeout })
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.channel.is_open()
+    }
+
+    /// Publica e aguarda a confirmação do broker.
+    /// Mensagens sem fila de destino (`basic.return`) exigem revisão manual; nack e timeout são transitórios.
+    pub async fn publish(&self, properties: BasicProperties, content: Vec<u8>, mut args: BasicPublishArguments) -> Result<(), Falha> {
+        args.mandatory = true;
+        let destino: String = format!("{}/{}", args.exchange, args.routing_key);
+        let (tx, rx) = oneshot::channel::<Confirmacao>();
+
+        let tag: u64 = {
+            let _envio = self.envio.lock().await;
+            let tag: u64 = match self.estado.lock() {
+                Ok(mut estado) => {
+                    let tag: u64 = estado.sequencia + 1;
+                    estado.pendentes.insert(tag, Pendente { tx, devolvida: None });
+                    tag
+                }
+                Err(e) => return Err(Falha::transitoria("publish_failed", e)),
+            };
+
+            // O contador só avança com a publicação aceita: um envio que falhou não recebe delivery tag.
+            let resultado = self.channel.basic_publish(com_delivery_tag(properties, tag), content, args).await;
+            if let Ok(mut estado) = self.estado.lock() {
+                match resultado {
+                    Ok(_) => estado.sequencia = tag,
+                    Err(_) => {
+                        estado.pendentes.remove(&tag);
+                    }
+                }
+            }
+            if let Err(e) = resultado {
+                return Err(Falha::transitoria("publish_failed", e));
+            }
+            tag
+        };
+
+        match timeout(self.timeout, rx).await {
+            Ok(Ok(Confirmacao::Ack)) => Ok(()),
+            Ok(Ok(Confirmacao::Nack)) => Err(Falha::transitoria("publish_nacked", format!("Broker nacked message to {}", destino))),
+            Ok(Ok(Confirmacao::Devolvida(motivo))) => Err(Falha::revisao_manual("publish_unroutable", format!("Message to {} returned: {}", destino, motivo))),
+            Ok(Err(_)) => Err(Falha::transitoria("publish_failed", format!("Channel closed before confirming message to {}", destino))),
+            Err(_) => {
+                if let Ok(mut estado) = self.estado.lock() {
+                    estado.pendentes.remove(&tag);
+                }
+                Err(Falha::transitoria("publish_timeout", format!("No confirmation for message to {} after {:?}", destino, self.timeout)))
+            }
+        }
+    }
+}
+
+/// Canal de publicação reaberto sob demanda. O broker fecha o canal em erros como uma exchange
+/// inexistente (404); a publicação seguinte abre um canal novo em vez de falhar para sempre.
+pub struct PublishChannel {
+    atual: tokio::sync::Mutex<Arc<ConfirmChannel>>,
+    connection: Connection,
+    queue: String,
+    routing_key: String,
+    exchange: String,
+    passive: bool,
+    timeout: Duration,
+}
+
+impl PublishChannel {
+    pub async fn open(connection: &Connection, queue: &str, routing_key: &str, exchange: &str, passive: bool, timeout: Duration) -> Result<PublishChannel, Box<dyn Error>> {
+        let canal: ConfirmChannel = abrir(connection, queue, routing_key, exchange, passive, timeout).await?;
+        Ok(PublishChannel {
+            atual: tokio::sync::Mutex::new(Arc::new(canal)),
+            connection: connection.clone(),
+            queue: queue.to_string(),
+            routing_key: routing_key.to_string(),
+            exchange: exchange.to_string(),
+            passive,
+            timeout,
+        })
+    }
+
+    pub async fn publish(&self, properties: BasicProperties, content: Vec<u8>, args: BasicPublishArguments) -> Result<(), Falha> {
+        self.canal().await?.publish(properties, content, args).await
+    }
+
+    async fn canal(&self) -> Result<Arc<ConfirmChannel>, Falha> {
+        let mut atual = self.atual.lock().await;
+        if atual.is_open() {
+            return Ok(atual.clone());
+        }
+
+        log::warn!("Publish channel for queue {} is closed, reopening", self.queue);
+        let canal: ConfirmChannel = abrir(&self.connection, &self.queue, &self.routing_key, &self.exchange, self.passive, self.timeout)
+            .await
+            .map_err(|e| Falha::transitoria("publish_channel_unavailable", e))?;
+        *atual = Arc::new(canal);
+        Ok(atual.clone())
+    }
+}
+
+async fn abrir(connection: &Connection, queue: &str, routing_key: &str, exchange: &str, passive: bool, timeout: Duration) -> Result<ConfirmChannel, Box<dyn Error>> {
+    let channel: Channel = initialize_publish_channel(queue, routing_key, exchange, passive, connection).await?;
+    Ok(ConfirmChannel::new(channel, timeout).await?)
+}
+
+struct ConfirmCallback {
+    estado: Arc<Mutex<EstadoConfirmacao>>,
+}
+
+#[async_trait]
+impl ChannelCallback for ConfirmCallback {
+    /// Publicações pendentes não serão mais confirmadas: falham de imediato, sem esperar o timeout.
+    async fn close(&mut self, channel: &Channel, close: CloseChannel) -> Result<(), AmqpError> {
+        log::error!("Publish channel {} closed by server: {}", channel.channel_id(), close);
+        if let Ok(mut estado) = self.estado.lock() {
+            estado.pendentes.clear();
+        }
+        Ok(())
+    }
+
+    async fn cancel(&mut self, _channel: &Channel, _cancel: Cancel) -> Result<(), AmqpError> {
+        Ok(())
+    }
+
+    async fn flow(&mut self, _channel: &Channel, _active: bool) -> Result<bool, AmqpError> {
+        Ok(true)
+    }
+
+    async fn publish_ack(&mut self, _channel: &Channel, ack: Ack) {
+        if let Ok(mut estado) = self.estado.lock() {
+            estado.resolver(ack.delivery_tag(), ack.mutiple(), false);
+        }
+    }
+
+    async fn publish_nack(&mut self, _channel: &Channel, nack: Nack) {
+        if let Ok(mut estado) = self.estado.lock() {
+            estado.resolver(nack.delivery_tag(), nack.multiple(), true);
+        }
+    }
+
+    async fn publish_return(&mut self, _channel: &Channel, ret: Return, basic_properties: BasicProperties, _content: Vec<u8>) {
+        log::warn!("Message returned by broker: {}", ret);
+        let Some(tag) = get_delivery_tag(&basic_properties) else {
+            log::error!("Returned message has no {} header, cannot match it to a publish", HEADER_PUBLICACAO);
+            return;
+        };
+        if let Ok(mut estado) = self.estado.lock() {
+            estado.devolver(tag, ret.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn devolucao_atinge_so_a_publicacao_do_cabecalho() {
+        let mut estado: EstadoConfirmacao = EstadoConfirmacao::default();
+        let mut recebidos = Vec::new();
+        for tag in 1..=2 {
+            let (tx, rx) = oneshot::channel::<Confirmacao>();
+            estado.pendentes.insert(tag, Pendente { tx, devolvida: None });
+            recebidos.push(rx);
+        }
+
+        // Duas publicações idênticas: o cabeçalho distingue qual foi devolvida.
+        let properties: BasicProperties = com_delivery_tag(BasicProperties::default(), 2);
+        estado.devolver(get_delivery_tag(&properties).unwrap(), "NO_ROUTE".to_string());
+        estado.resolver(2, true, false);
+
+        assert!(matches!(recebidos[0].try_recv(), Ok(Confirmacao::Ack)));
+        assert!(matches!(recebidos[1].try_recv(), Ok(Confirmacao::Devolvida(_))));
+    }
+}
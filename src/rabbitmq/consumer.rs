@@ -1,22 +1,34 @@
-use std::{error::Error, sync::Arc, time::Duration};
+use std::{error::Error, pin::pin, process::ExitCode, sync::Arc, time::{Duration, Instant}};
 
 use amqprs::{
     channel::{
-        BasicAckArguments, BasicConsumeArguments, BasicPublishArguments, BasicRejectArguments, Channel
+        BasicAckArguments, BasicCancelArguments, BasicConsumeArguments, BasicPublishArguments, BasicRejectArguments, Channel
     }, connection::Connection, consumer::AsyncConsumer, BasicProperties, Deliver
 };
 use async_trait::async_trait;
 use bytes::Bytes;
-use tokio::{time::sleep};
+use tokio::{signal::{self, unix::{SignalKind, signal}}, sync::Semaphore, time::{sleep, timeout}};
 
-use crate::{minio_client, nfe_parser::parse_xml, rabbitmq::{self, common::{Message, RabbitVariables}}};
+use crate::{estado, falha::{ClasseFalha, Falha}, metricas, minio_client, nfe_parser::{Identificacao, ResultadoParse}, parse_pool, rabbitmq::{self, common::{Message, RabbitVariables}, confirm::PublishChannel, dead_letter::with_falha, properties::{json_properties, result_properties}, retry::{get_tentativas, retry_queue_name, with_tentativas}, routing::Roteamento}};
 
 
+/// Intervalo entre os resumos de métricas no log.
+const INTERVALO_METRICAS: Duration = Duration::from_secs(60);
+
 // Implementa async consumer
 pub struct XmlConsumer {
     publish_args: BasicPublishArguments,
-    publish_channel: Channel,
+    routing: Roteamento,
+    /// Canal em modo confirm: a mensagem consumida só é confirmada após o ack do broker.
+    publish_channel: PublishChannel,
     bucket_name: String,
+    /// Canal das mudanças de situação, aberto apenas com o banco de estado habilitado.
+    status: Option<(PublishChannel, BasicPublishArguments)>,
+    consume_queue: String,
+    retry_delays: Vec<u32>,
+    parking_queue: String,
+    dead_letter_args: BasicPublishArguments,
+    result_headers: Vec<String>,
 }
 
 impl XmlConsumer {
@@ -25,16 +37,32 @@ impl XmlConsumer {
         let args: BasicPublishArguments = BasicPublishArguments {
             exchange: variables.exchange.clone(),
             routing_key: variables.routing_key.clone(),
-            mandatory: false,
+            mandatory: true,
             immediate: false
         };
 
-        let channel: Channel = rabbitmq::common::initialize_publish_channel(&variables.publish_queue, &variables.routing_key, &variables.exchange, &connection).await?;
+        let publish_channel: PublishChannel = PublishChannel::open(connection, &variables.publish_queue, &variables.routing_key, &variables.exchange, variables.passive, variables.confirm_timeout).await?;
+
+        let status: Option<(PublishChannel, BasicPublishArguments)> = match estado::get_store() {
+            Some(_) => {
+                let status_args: BasicPublishArguments = BasicPublishArguments::new(&variables.exchange, &variables.status_routing_key);
+                let status_channel: PublishChannel = PublishChannel::open(connection, &variables.status_queue, &variables.status_routing_key, &variables.exchange, variables.passive, variables.confirm_timeout).await?;
+                Some((status_channel, status_args))
+            }
+            None => None,
+        };
 
         Ok(Self {
             publish_args: args,
-            publish_channel: channel,
+            routing: variables.routing.clone(),
+            publish_channel,
             bucket_name: bucket_name.clone(),
+            status,
+            consume_queue: variables.consume_queue.clone(),
+            retry_delays: variables.retry_delays.clone(),
+            parking_queue: variables.parking_queue.clone(),
+            dead_letter_args: BasicPublishArguments::new(&variables.dead_letter.exchange, &variables.dead_letter.routing_key),
+            result_headers: variables.result_headers.clone(),
         })
     }
 }
@@ -44,6 +72,14 @@ pub struct RabbitMqConsumer {
     minio_bucket_name: String,
     connection: Arc<Connection>,
     consumer_channels: Vec<Channel>,
+    /// Consumidores registrados, cancelados e drenados no encerramento.
+    registros: Vec<Registro>,
+}
+
+struct Registro {
+    channel: Channel,
+    consumer_tag: String,
+    vagas: Arc<Semaphore>,
 }
 
 impl RabbitMqConsumer {
@@ -55,31 +91,106 @@ impl RabbitMqConsumer {
             minio_bucket_name: minio_bucket_name,
             connection: connection,
             consumer_channels: Vec::new(),
+            registros: Vec::new(),
         }
 
 
     }
 
-    pub async fn start(&mut self) {
+    /// Consome até receber SIGTERM ou SIGINT e então encerra de forma ordenada.
+    pub async fn start(&mut self) -> ExitCode {
         if self.connection.is_open() {
+            if let Err(e) = rabbitmq::common::declare_routing_exchanges(&self.variables, &self.connection).await {
+                panic!("Could not declare routing exchanges: {}", e);
+            }
             self.initialize_channels().await;
-            self.register_consuming_channels().await.ok();
+            if let Err(e) = self.register_consuming_channels().await {
+                log::error!("Failed to register consumers: {}", e);
+            }
         }
 
+        let mut encerramento = pin!(sinal_encerramento());
+        let mut ultimo_resumo: Instant = Instant::now();
         loop {
-            if !self.connection.is_open() {
-                log::warn!("Connection closed, restarting...");
-                match self.restart().await {
-                    Ok(_) => log::info!("Restart successful."),
-                    Err(e) => log::error!("Failed to restart: {}", e),
-                }
+            let sinal: Option<&'static str> = tokio::select! {
+                sinal = &mut encerramento => Some(sinal),
+                _ = self.monitorar(&mut ultimo_resumo) => None,
+            };
+            if let Some(sinal) = sinal {
+                log::info!("Received {}, shutting down", sinal);
+                return self.shutdown().await;
+            }
+        }
+    }
+
+    /// Publica as métricas, reconecta se a conexão caiu e aguarda o próximo ciclo.
+    async fn monitorar(&mut self, ultimo_resumo: &mut Instant) {
+        if ultimo_resumo.elapsed() >= INTERVALO_METRICAS {
+            log::info!("Metrics | {}", metricas::resumo());
+            *ultimo_resumo = Instant::now();
+        }
+
+        if !self.connection.is_open() {
+            log::warn!("Connection closed, restarting...");
+            match self.restart().await {
+                Ok(_) => log::info!("Restart successful."),
+                Err(e) => log::error!("Failed to restart: {}", e),
+            }
+        }
+        sleep(Duration::from_secs(5)).await;
+    }
+
+    /// Cancela os consumidores, aguarda as mensagens em andamento até `RABBITMQ_SHUTDOWN_TIMEOUT_MS`
+    /// e fecha canais e conexão. Retorna falha se o prazo esgotar com mensagens em andamento,
+    /// que serão reentregues pelo broker.
+    async fn shutdown(&mut self) -> ExitCode {
+        for registro in self.registros.iter() {
+            if let Err(e) = registro.channel.basic_cancel(BasicCancelArguments::new(&registro.consumer_tag)).await {
+                log::warn!("Failed to cancel consumer on channel {}: {}", registro.channel.channel_id(), e);
+            }
+        }
+
+        let concorrencia: usize = self.variables.channel_concurrency;
+        let em_andamento: usize = self.registros.iter().map(|r| concorrencia - r.vagas.available_permits()).sum();
+        log::info!("Consumers cancelled, waiting up to {:?} for {} in-flight messages", self.variables.shutdown_timeout, em_andamento);
+
+        // Com todas as vagas ocupadas pelo encerramento, o semáforo é fechado: entregas que
+        // ainda cheguem não são processadas e voltam para a fila quando o canal fecha.
+        let drenar = async {
+            for registro in self.registros.iter() {
+                let _vagas = registro.vagas.acquire_many(concorrencia as u32).await;
+                registro.vagas.close();
+            }
+        };
+        let drenado: bool = timeout(self.variables.shutdown_timeout, drenar).await.is_ok();
+        if !drenado {
+            let restantes: usize = self.registros.iter().map(|r| concorrencia - r.vagas.available_permits()).sum();
+            log::error!("Shutdown deadline exceeded with {} messages in flight, they will be redelivered", restantes);
+        }
+
+        for registro in self.registros.drain(..) {
+            if let Err(e) = registro.channel.close().await {
+                log::warn!("Failed to close channel: {}", e);
             }
-            sleep(Duration::from_secs(5)).await;
+        }
+        self.consumer_channels.clear();
+        if let Err(e) = self.connection.as_ref().clone().close().await {
+            log::warn!("Failed to close connection: {}", e);
+        }
+
+        log::info!("Metrics | {}", metricas::resumo());
+        match drenado {
+            true => {
+                log::info!("Shutdown complete");
+                ExitCode::SUCCESS
+            }
+            false => ExitCode::FAILURE,
         }
     }
 
     async fn restart(&mut self) -> Result<(), Box<dyn Error>> {
         self.consumer_channels.clear();
+        self.registros.clear();
         self.connection = rabbitmq::common::connect_rabbitmq(&self.variables).await;
         self.initialize_channels().await;
         self.register_consuming_channels().await?;
@@ -88,12 +199,7 @@ impl RabbitMqConsumer {
     }
 
     async fn initialize_channels(&mut self) {
-        match rabbitmq::common::initialize_channels(
-            &self.variables.consume_queue,
-            &self.variables.routing_key,
-            &mut self.variables.exchange,
-            self.variables.num_channels,
-             &self.connection).await {
+        match rabbitmq::common::initialize_channels(&self.variables, &self.connection).await {
             Ok(v) => self.consumer_channels = v,
             Err(e) => {
                 log::error!("Failed to initialize channels: {}", e);
@@ -101,7 +207,7 @@ impl RabbitMqConsumer {
         }
     }
 
-    async fn register_consuming_channels(&self) -> Result<(), Box<dyn Error>> {
+    async fn register_consuming_channels(&mut self) -> Result<(), Box<dyn Error>> {
         for channel in self.consumer_channels.iter() {
             // Consumer tag deve vir de rabbit variables.
             let args: BasicConsumeArguments =
@@ -111,8 +217,11 @@ impl RabbitMqConsumer {
                     .manual_ack(true).finish();
             
 
-            let consume: XmlConsumer = XmlConsumer::new(&self.variables, &self.minio_bucket_name, &self.connection).await?;
-            channel.basic_consume(consume, args).await?;
+            let consumer: XmlConsumer = XmlConsumer::new(&self.variables, &self.minio_bucket_name, &self.connection).await?;
+            let consume: ConcurrentConsumer = ConcurrentConsumer::new(consumer, self.variables.channel_concurrency);
+            let vagas: Arc<Semaphore> = consume.vagas.clone();
+            let consumer_tag: String = channel.basic_consume(consume, args).await?;
+            self.registros.push(Registro { channel: channel.clone(), consumer_tag, vagas });
         }
         log::debug!("Successfully registered consuming channels");
         Ok(())
@@ -120,19 +229,144 @@ impl RabbitMqConsumer {
 }
 
 impl XmlConsumer {
-    async fn publish (&self, message: Vec<u8> ) -> bool {
-        
-        let result = self.publish_channel.basic_publish(
-            BasicProperties::default(), message, self.publish_args.clone()).await;
+    /// Publica o resultado e aguarda a confirmação. Um resultado sem fila de destino
+    /// vira falha de revisão manual, e a mensagem original segue para a fila de revisão.
+    async fn publish (&self, identificacao: &Identificacao, properties: BasicProperties, message: Vec<u8> ) -> Result<(), Falha> {
+        let args: BasicPublishArguments = self.routing.publish_args(identificacao, &self.publish_args);
+        self.publish_channel.publish(properties, message, args).await
+    }
 
-        match result {
-            Ok(_) =>  return true,
-            
+    /// Publica as mudanças de situação pendentes no outbox, em ordem de gravação, removendo cada uma
+    /// após a confirmação. Uma falha interrompe o envio; o restante segue no outbox para a próxima mensagem.
+    async fn publish_status(&self) {
+        let Some((channel, args)) = &self.status else {
+            return;
+        };
+        let _envio = estado::travar_envio_status().await;
+
+        let pendentes: Vec<estado::StatusPendente> = match estado::executar(|store| store.status_pendentes()).await {
+            Ok(pendentes) => pendentes.unwrap_or_default(),
             Err(e) => {
-                log::error!("Failed to publish message: {}", e);
-                return false;
+                log::error!("Failed to read pending status changes: {}", e);
+                estado::marcar_status_pendente(true);
+                return;
+            }
+        };
+
+        for (id, payload) in pendentes {
+            if let Err(e) = channel.publish(json_properties(), payload, args.clone()).await {
+                log::error!("Failed to publish status change, keeping it for the next message | id: {} | {}", id, e);
+                estado::marcar_status_pendente(true);
+                return;
+            }
+            if let Err(e) = estado::executar(move |store| store.remover_status(id)).await {
+                log::error!("Failed to remove published status change | id: {} | {}", id, e);
+                estado::marcar_status_pendente(true);
+                return;
             }
         }
+        estado::marcar_status_pendente(false);
+    }
+
+    /// Decodifica a mensagem, baixa o XML, faz o parse e publica o resultado.
+    async fn process(&self, content: &[u8], properties: &BasicProperties) -> Result<Message, Falha> {
+        let content_json: &str = std::str::from_utf8(content)
+            .map_err(|e| Falha::permanente("invalid_utf8", e))?;
+
+        let message: Message = serde_json::from_str::<Message>(content_json)
+            .map_err(|e| Falha::permanente("invalid_message", format!("{} | Content: {}", e, content_json)))?;
+
+        let file: Bytes = minio_client::download_object(&message.file, &self.bucket_name).await
+            .map_err(|f| f.com_arquivo(&message.file))?;
+
+        let ResultadoParse { json: json_bytes, ocorrencias, identificacao } = parse_pool::parse(file, message.company_id, message.org_id).await
+            .map_err(|f| f.com_arquivo(&message.file))?;
+
+        // As chaves ficam travadas até o estado ser gravado: outra mensagem da mesma NF-e só confere
+        // a projeção depois desta. Ocorrências recusadas (CC-e fora de ordem) não são publicadas.
+        let _bloqueio = estado::bloquear(&ocorrencias).await;
+        let ocorrencias = estado::executar(move |store| store.verificar(&ocorrencias).map(|_| ocorrencias)).await
+            .map_err(|f| f.com_arquivo(&message.file))?
+            .unwrap_or_default();
+
+        let result_properties: BasicProperties = result_properties(&identificacao, &message, properties, &json_bytes, &self.result_headers);
+        self.publish(&identificacao, result_properties, json_bytes).await.map_err(|f| f.com_arquivo(&message.file))?;
+
+        // O estado só é gravado com o resultado confirmado. Se a gravação falhar, a mensagem volta
+        // pela fila de retentativa e o resultado é publicado de novo.
+        let (company_id, org_id) = (message.company_id, message.org_id);
+        let mudancas: usize = estado::executar(move |store| store.aplicar(&ocorrencias, company_id, org_id)).await
+            .map_err(|f| f.com_arquivo(&message.file))?
+            .unwrap_or_default();
+
+        if mudancas > 0 || estado::status_pendente() {
+            self.publish_status().await;
+        }
+        Ok(message)
+    }
+
+    /// Registra a falha e dá à mensagem o destino da sua classe.
+    async fn handle_failure(&self, channel: &Channel, deliver: Deliver, properties: &BasicProperties, content: Vec<u8>, falha: Falha) {
+        log::error!("Failed to process message | class: {} | reason: {} | file: {} | {}",
+            falha.classe, falha.motivo, falha.arquivo.as_deref().unwrap_or("-"), falha.erro);
+        metricas::registrar_falha(&falha);
+
+        match falha.classe {
+            ClasseFalha::Transitoria => self.retry_message(channel, deliver, properties, content, &falha).await,
+            ClasseFalha::Permanente => self.dead_letter_message(channel, deliver, properties, content, &falha).await,
+            ClasseFalha::RevisaoManual => self.park_message(channel, deliver, properties, content).await,
+        }
+    }
+
+    /// Envia a mensagem para a fila de espera da próxima tentativa e confirma a original.
+    /// Esgotadas as tentativas, a mensagem vai para a Dead Letter Queue.
+    /// Se o encaminhamento falhar, a mensagem é rejeitada e segue para a DLX da fila:
+    /// devolvê-la à fila a reprocessaria imediatamente, sem atraso.
+    async fn retry_message(&self, channel: &Channel, deliver: Deliver, properties: &BasicProperties, content: Vec<u8>, falha: &Falha) {
+        let tentativas: u32 = get_tentativas(properties, &self.consume_queue);
+        let Some(&atraso) = self.retry_delays.get(tentativas as usize) else {
+            log::warn!("Retries exhausted after {} attempts, dead-lettering", tentativas);
+            return self.dead_letter_message(channel, deliver, properties, content, falha).await;
+        };
+
+        let queue: String = retry_queue_name(&self.consume_queue, atraso);
+        log::warn!("Retrying in {} ms (attempt {} of {})", atraso, tentativas + 1, self.retry_delays.len());
+        let args: BasicPublishArguments = BasicPublishArguments::new("", &queue);
+        if !self.forward_message(channel, &deliver, args, with_tentativas(properties, tentativas + 1), content).await {
+            self.reject_message(channel, deliver).await;
+        }
+    }
+
+    /// Move a mensagem para a fila de revisão manual. Se o encaminhamento falhar, rejeita a original.
+    async fn park_message(&self, channel: &Channel, deliver: Deliver, properties: &BasicProperties, content: Vec<u8>) {
+        let args: BasicPublishArguments = BasicPublishArguments::new("", &self.parking_queue);
+        if !self.forward_message(channel, &deliver, args, properties.clone(), content).await {
+            self.reject_message(channel, deliver).await;
+        }
+    }
+
+    /// Republica a mensagem na DLX com os metadados da falha nos cabeçalhos.
+    /// Se a publicação falhar, rejeita a original e a DLX recebe a mensagem sem os metadados.
+    async fn dead_letter_message(&self, channel: &Channel, deliver: Deliver, properties: &BasicProperties, content: Vec<u8>, falha: &Falha) {
+        let tentativas: u32 = get_tentativas(properties, &self.consume_queue);
+        let properties: BasicProperties = with_falha(properties, falha, tentativas, &self.consume_queue);
+        if !self.forward_message(channel, &deliver, self.dead_letter_args.clone(), properties, content).await {
+            self.reject_message(channel, deliver).await;
+        }
+    }
+
+    /// Publica a mensagem e confirma a original. Retorna `false` se a publicação
+    /// falhar, não for confirmada pelo broker ou não tiver fila de destino.
+    async fn forward_message(&self, channel: &Channel, deliver: &Deliver, args: BasicPublishArguments, properties: BasicProperties, content: Vec<u8>) -> bool {
+        if let Err(falha) = self.publish_channel.publish(properties, content, args.clone()).await {
+            log::error!("Failed to forward message to {}/{}: {}", args.exchange, args.routing_key, falha);
+            return false;
+        }
+        let args: BasicAckArguments = BasicAckArguments::new(deliver.delivery_tag(), false);
+        if let Err(e) = channel.basic_ack(args).await {
+            log::error!("Could not ack message: {e}");
+        }
+        true
     }
 
     async fn reject_message(&self, channel: &Channel, deliver: Deliver) {
@@ -152,56 +386,67 @@ impl Drop for XmlConsumer {
         println!("XmlConsumer is being dropped!");
     }
 }
-#[async_trait]
-impl AsyncConsumer for XmlConsumer {
-    async fn consume(&mut self, channel: &Channel, deliver: Deliver, _basic_properties: BasicProperties, content: Vec<u8>) {
-        
-        //let current_thread: ThreadId = thread::current().id();
-        log::debug!("Consuming on channel: {}", channel.channel_id());
-        
 
-        let content_json = match std::str::from_utf8(&content) {
-            Ok(v) => v,
-            Err(e) => {
-                log::error!("{}", e);
-                return self.reject_message(&channel, deliver).await;
-            }
-        };
+/// Processa até `concorrencia` mensagens do canal ao mesmo tempo. Sem vaga livre, o consumo
+/// aguarda, e o broker retém as demais entregas conforme o prefetch.
+pub struct ConcurrentConsumer {
+    consumer: Arc<XmlConsumer>,
+    vagas: Arc<Semaphore>,
+}
 
-        let message: Message = match serde_json::from_str::<Message>(&content_json) {
-            Ok(m) => m,
-            Err(e) => {
-                let content_str = String::from_utf8_lossy(&content);
-                log::error!("Failed to decode message: {} | Content: {}", e, content_str);
-                return self.reject_message(&channel, deliver).await;
-            }
-        };
+/// Aguarda SIGTERM (`docker stop`) ou SIGINT (Ctrl+C).
+async fn sinal_encerramento() -> &'static str {
+    let mut sigterm = match signal(SignalKind::terminate()) {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!("Could not listen for SIGTERM: {}", e);
+            signal::ctrl_c().await.ok();
+            return "SIGINT";
+        }
+    };
+    tokio::select! {
+        _ = sigterm.recv() => "SIGTERM",
+        _ = signal::ctrl_c() => "SIGINT",
+    }
+}
 
-        let result =  minio_client::download_object(&message.file, &self.bucket_name).await
-            .map_err(|e| {log::error!("Failed to download MinIO object: {}", e);});
-        let file: Bytes = match result {
-            Ok(f) => f,
-            Err(_) => return self.reject_message(channel, deliver).await
-        };
-      
-        let result: Result<Vec<u8>, ()> = parse_xml(file, message.company_id, message.org_id)
-            .map_err(|e| {log::error!("Failed: {}", e);});
+impl ConcurrentConsumer {
+    pub fn new(consumer: XmlConsumer, concorrencia: usize) -> Self {
+        Self { consumer: Arc::new(consumer), vagas: Arc::new(Semaphore::new(concorrencia)) }
+    }
+}
 
-        let json_bytes = match result {
-            Ok(v) => v,
-            Err(_) => return self.reject_message(&channel, deliver).await
+#[async_trait]
+impl AsyncConsumer for ConcurrentConsumer {
+    async fn consume(&mut self, channel: &Channel, deliver: Deliver, basic_properties: BasicProperties, content: Vec<u8>) {
+        let Ok(vaga) = self.vagas.clone().acquire_owned().await else {
+            return;
         };
+        let consumer: Arc<XmlConsumer> = self.consumer.clone();
+        let channel: Channel = channel.clone();
+        tokio::spawn(async move {
+            consumer.consume(&channel, deliver, basic_properties, content).await;
+            drop(vaga);
+        });
+    }
+}
 
-        let result: bool = self.publish(json_bytes).await;
-        if !result {
-            return self.reject_message(&channel, deliver).await;
-        }
+impl XmlConsumer {
+    async fn consume(&self, channel: &Channel, deliver: Deliver, basic_properties: BasicProperties, content: Vec<u8>) {
         
+        //let current_thread: ThreadId = thread::current().id();
+        log::debug!("Consuming on channel: {}", channel.channel_id());
+
+        let message: Message = match self.process(&content, &basic_properties).await {
+            Ok(m) => m,
+            Err(falha) => return self.handle_failure(channel, deliver, &basic_properties, content, falha).await,
+        };
 
         let args: BasicAckArguments = BasicAckArguments::new(deliver.delivery_tag(), false);
 
         match channel.basic_ack(args).await {
             Ok(_) => {
+                metricas::registrar_sucesso();
                 log::info!("Message processed successfully | file: {} | company_id: {} | org_id: {}", 
                 message.file, message.company_id, message.org_id);
             }
@@ -0,0 +1,118 @@
+use std::{env, fs, sync::OnceLock};
+
+use amqprs::{BasicProperties, FieldTable, FieldValue};
+
+use crate::falha::Falha;
+
+pub const TIPOS_EXCHANGE: [&str; 4] = ["direct", "fanout", "topic", "headers"];
+
+/// Topologia de dead-letter da fila de consumo. Nomes aceitam `{queue}`, substituído pela fila de consumo.
+#[derive(Clone)]
+pub struct DeadLetterVariables {
+    pub exchange: String,
+    pub exchange_type: String,
+    pub routing_key: String,
+    pub queue: String,
+    /// Argumentos de declaração da DLQ (ex.: `x-queue-type=quorum`).
+    pub queue_arguments: FieldTable,
+}
+
+pub fn initialize_variables(consume_queue: &str) -> DeadLetterVariables {
+    let get = |var: &str, padrao: &str| -> String {
+        env::var(var)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or(padrao.to_string())
+            .replace("{queue}", consume_queue)
+    };
+
+    let exchange_type: String = get("RABBITMQ_DLX_TYPE", "direct").to_lowercase();
+    if !TIPOS_EXCHANGE.contains(&exchange_type.as_str()) {
+        panic!("Invalid RABBITMQ_DLX_TYPE '{}', expected one of {:?}", exchange_type, TIPOS_EXCHANGE);
+    }
+    let queue: String = get("RABBITMQ_DLQ_QUEUE", "dead_letter_queue");
+
+    DeadLetterVariables {
+        exchange: get("RABBITMQ_DLX_EXCHANGE", "dead_letter_exchange"),
+        exchange_type,
+        routing_key: get("RABBITMQ_DLX_ROUTING_KEY", &queue),
+        queue,
+        queue_arguments: get_queue_arguments("RABBITMQ_DLQ_ARGUMENTS"),
+    }
+}
+
+/// Lista `chave=valor` separada por vírgula. Inteiros e booleanos mantêm o tipo; o resto vira texto.
+fn get_queue_arguments(var: &str) -> FieldTable {
+    let mut table: FieldTable = FieldTable::new();
+    let Ok(valor) = env::var(var) else {
+        return table;
+    };
+    for par in valor.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let Some((chave, valor)) = par.split_once('=') else {
+            panic!("Invalid {}: expected key=value, found '{}'", var, par);
+        };
+        let valor: &str = valor.trim();
+        let valor: FieldValue = match (valor.parse::<i64>(), valor.parse::<bool>()) {
+            (Ok(n), _) => FieldValue::l(n),
+            (_, Ok(b)) => FieldValue::t(b),
+            _ => valor.into(),
+        };
+        match chave.trim().try_into() {
+            Ok(chave) => table.insert(chave, valor),
+            Err(e) => panic!("Invalid {}: {}", var, e),
+        };
+    }
+    table
+}
+
+/// Tamanho máximo da mensagem de erro no cabeçalho.
+const MAX_ERRO: usize = 1024;
+
+static HOST: OnceLock<String> = OnceLock::new();
+
+/// Cópia das propriedades com os metadados da falha, para a mensagem republicada na DLX.
+pub fn with_falha(properties: &BasicProperties, falha: &Falha, tentativas: u32, queue: &str) -> BasicProperties {
+    let mut headers: FieldTable = properties.headers().cloned().unwrap_or_default();
+
+    let mut inserir = |chave: &str, valor: FieldValue| {
+        if let Ok(chave) = chave.try_into() {
+            headers.insert(chave, valor);
+        }
+    };
+    inserir("x-error-class", falha.classe.as_str().into());
+    inserir("x-error-reason", falha.motivo.into());
+    inserir("x-error-message", truncar(&falha.erro, MAX_ERRO).into());
+    if let Some(caminho) = &falha.caminho {
+        inserir("x-error-path", caminho.as_str().into());
+    }
+    if let Some(arquivo) = &falha.arquivo {
+        inserir("x-file", arquivo.as_str().into());
+    }
+    inserir("x-parser-version", env!("CARGO_PKG_VERSION").into());
+    inserir("x-consumer-host", get_host().into());
+    inserir("x-retry-count", FieldValue::I(tentativas as i32));
+    inserir("x-original-queue", queue.into());
+
+    let mut properties: BasicProperties = properties.clone();
+    properties.with_headers(headers);
+    properties
+}
+
+/// `HOSTNAME` ou `/etc/hostname` (no container, o id do container).
+fn get_host() -> &'static str {
+    HOST.get_or_init(|| {
+        env::var("HOSTNAME")
+            .ok()
+            .or_else(|| fs::read_to_string("/etc/hostname").ok())
+            .map(|h| h.trim().to_string())
+            .filter(|h| !h.is_empty())
+            .unwrap_or("unknown".to_string())
+    })
+}
+
+fn truncar(texto: &str, max: usize) -> &str {
+    match texto.char_indices().nth(max) {
+        Some((i, _)) => &texto[..i],
+        None => texto,
+    }
+}
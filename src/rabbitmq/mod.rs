@@ -1,2 +1,7 @@
 pub mod consumer;
-pub mod common;
\ No newline at end of file
+pub mod common;
+pub mod confirm;
+pub mod dead_letter;
+pub mod properties;
+pub mod retry;
+pub mod routing;
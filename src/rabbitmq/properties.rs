@@ -0,0 +1,79 @@
+use std::{env, time::{SystemTime, UNIX_EPOCH}};
+
+use amqprs::{BasicProperties, FieldTable, FieldValue};
+
+use crate::{nfe_parser::Identificacao, rabbitmq::common::Message};
+
+/// Cabeçalhos que podem acompanhar o resultado publicado.
+const CABECALHOS: [&str; 8] = ["company_id", "org_id", "file", "doc_type", "doc_id", "modelo", "tpAmb", "uf"];
+const CABECALHOS_PADRAO: &str = "company_id,org_id,file";
+
+/// Lista de cabeçalhos do resultado, separada por vírgula. Vazia desabilita os cabeçalhos.
+pub fn get_result_headers() -> Vec<String> {
+    let valor: String = env::var("RABBITMQ_RESULT_HEADERS").unwrap_or(CABECALHOS_PADRAO.to_string());
+    let cabecalhos: Vec<String> = valor
+        .split(',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+
+    if let Some(invalido) = cabecalhos.iter().find(|c| !CABECALHOS.contains(&c.as_str())) {
+        panic!("Invalid RABBITMQ_RESULT_HEADERS '{}', expected any of {:?}", invalido, CABECALHOS);
+    }
+    cabecalhos
+}
+
+/// Propriedades comuns às mensagens JSON publicadas: persistentes, com tipo de conteúdo, aplicação e horário.
+pub fn json_properties() -> BasicProperties {
+    let timestamp: u64 = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+
+    let mut properties: BasicProperties = BasicProperties::default();
+    properties
+        .with_persistence(true)
+        .with_content_type("application/json")
+        .with_app_id(env!("CARGO_PKG_NAME"))
+        .with_timestamp(timestamp);
+    properties
+}
+
+/// Propriedades do resultado do parse. O `message_id` é a chave de acesso (ou o Id do evento) e, em lotes,
+/// um hash do conteúdo; o `correlation_id` vem da mensagem consumida.
+pub fn result_properties(identificacao: &Identificacao, message: &Message, recebida: &BasicProperties, json: &[u8], cabecalhos: &[String]) -> BasicProperties {
+    let message_id: String = match &identificacao.id {
+        Some(id) => id.clone(),
+        None => format!("{:016x}", fnv1a(json)),
+    };
+
+    let mut properties: BasicProperties = json_properties();
+    properties.with_message_id(&message_id).with_message_type(identificacao.tipo);
+    if let Some(correlation_id) = recebida.correlation_id().or(recebida.message_id()) {
+        properties.with_correlation_id(correlation_id);
+    }
+
+    if !cabecalhos.is_empty() {
+        let mut headers: FieldTable = FieldTable::new();
+        for cabecalho in cabecalhos {
+            let valor: FieldValue = match cabecalho.as_str() {
+                "company_id" => FieldValue::l(message.company_id),
+                "org_id" => FieldValue::l(message.org_id),
+                "file" => message.file.as_str().into(),
+                "doc_type" => identificacao.tipo.into(),
+                "doc_id" => message_id.as_str().into(),
+                "modelo" => identificacao.modelo.as_deref().unwrap_or_default().into(),
+                "tpAmb" => identificacao.tpAmb.as_deref().unwrap_or_default().into(),
+                "uf" => identificacao.uf.as_deref().unwrap_or_default().into(),
+                _ => continue,
+            };
+            if let Ok(chave) = cabecalho.as_str().try_into() {
+                headers.insert(chave, valor);
+            }
+        }
+        properties.with_headers(headers);
+    }
+    properties
+}
+
+/// FNV-1a de 64 bits: estável entre versões e execuções, ao contrário do `DefaultHasher`.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
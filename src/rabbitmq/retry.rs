@@ -0,0 +1,109 @@
+use std::error::Error;
+
+use amqprs::{
+    channel::{Channel, QueueDeclareArguments},
+    BasicProperties, FieldTable, FieldValue,
+};
+
+use crate::rabbitmq::common::get_var_or;
+
+/// Cabeçalho com o número de tentativas já realizadas.
+const HEADER_TENTATIVAS: &str = "x-retry-count";
+
+/// Atrasos (ms) de cada tentativa, em progressão geométrica:
+/// `RABBITMQ_RETRY_DELAY_MS * RABBITMQ_RETRY_MULTIPLIER^n` para `n < RABBITMQ_RETRY_MAX_ATTEMPTS`.
+/// Com `RABBITMQ_RETRY_MAX_ATTEMPTS=0` as falhas vão direto para a Dead Letter Queue.
+pub fn get_retry_delays() -> Vec<u32> {
+    let max_tentativas: u32 = get_var_or("RABBITMQ_RETRY_MAX_ATTEMPTS", 5);
+    let atraso_inicial: u32 = get_var_or("RABBITMQ_RETRY_DELAY_MS", 5000);
+    let multiplicador: u32 = get_var_or("RABBITMQ_RETRY_MULTIPLIER", 3);
+
+    if max_tentativas > 0 && (atraso_inicial == 0 || multiplicador == 0) {
+        panic!("RABBITMQ_RETRY_DELAY_MS and RABBITMQ_RETRY_MULTIPLIER must be greater than zero");
+    }
+
+    (0..max_tentativas)
+        .map(|n| {
+            multiplicador
+                .checked_pow(n)
+                .and_then(|m| m.checked_mul(atraso_inicial))
+                .unwrap_or_else(|| panic!("Retry delay overflow at attempt {}", n + 1))
+        })
+        .collect()
+}
+
+pub fn retry_queue_name(queue: &str, atraso: u32) -> String {
+    format!("{}.retry.{}", queue, atraso)
+}
+
+/// Declara uma fila de espera por atraso. Ao expirar o TTL, a mensagem volta
+/// para `queue` pela default exchange.
+pub async fn declare_retry_queues(channel: &Channel, queue: &str, atrasos: &[u32]) -> Result<(), Box<dyn Error>> {
+    for &atraso in atrasos {
+        let mut table: FieldTable = FieldTable::new();
+        table.insert("x-message-ttl".try_into()?, FieldValue::I(i32::try_from(atraso)?));
+        table.insert("x-dead-letter-exchange".try_into()?, "".into());
+        table.insert("x-dead-letter-routing-key".try_into()?, queue.into());
+
+        let args: QueueDeclareArguments = QueueDeclareArguments::durable_client_named(&retry_queue_name(queue, atraso))
+            .arguments(table)
+            .finish();
+        channel.queue_declare(args).await?;
+    }
+    Ok(())
+}
+
+/// Tentativas já feitas: o maior valor entre o cabeçalho `x-retry-count`
+/// e as expirações registradas em `x-death` para as filas de espera de `queue`.
+pub fn get_tentativas(properties: &BasicProperties, queue: &str) -> u32 {
+    let Some(headers) = properties.headers() else {
+        return 0;
+    };
+
+    let contador: u32 = match headers.get(&HEADER_TENTATIVAS.try_into().unwrap()) {
+        Some(valor) => get_inteiro(valor).unwrap_or(0),
+        None => 0,
+    };
+
+    let prefixo: String = format!("{}.retry.", queue);
+    let mortes: u32 = match headers.get(&"x-death".try_into().unwrap()) {
+        Some(FieldValue::A(array)) => Vec::<FieldValue>::from(array.clone())
+            .iter()
+            .filter_map(|morte| match morte {
+                FieldValue::F(morte) => Some(morte),
+                _ => None,
+            })
+            .filter(|morte| {
+                let fila: Option<&str> = morte.get(&"queue".try_into().unwrap()).and_then(|q| q.try_into().ok());
+                fila.is_some_and(|f| f.starts_with(&prefixo))
+            })
+            .filter_map(|morte| morte.get(&"count".try_into().unwrap()).and_then(get_inteiro))
+            .sum(),
+        _ => 0,
+    };
+
+    contador.max(mortes)
+}
+
+/// Cópia das propriedades com o contador de tentativas atualizado.
+pub fn with_tentativas(properties: &BasicProperties, tentativas: u32) -> BasicProperties {
+    let mut headers: FieldTable = properties.headers().cloned().unwrap_or_default();
+    headers.insert(HEADER_TENTATIVAS.try_into().unwrap(), FieldValue::I(tentativas as i32));
+
+    let mut properties: BasicProperties = properties.clone();
+    properties.with_headers(headers);
+    properties
+}
+
+fn get_inteiro(valor: &FieldValue) -> Option<u32> {
+    match valor {
+        FieldValue::b(n) => u32::try_from(*n).ok(),
+        FieldValue::B(n) => Some(u32::from(*n)),
+        FieldValue::s(n) => u32::try_from(*n).ok(),
+        FieldValue::u(n) => Some(u32::from(*n)),
+        FieldValue::I(n) => u32::try_from(*n).ok(),
+        FieldValue::i(n) => Some(*n),
+        FieldValue::l(n) => u32::try_from(*n).ok(),
+        _ => None,
+    }
+}
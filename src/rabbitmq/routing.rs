@@ -0,0 +1,95 @@
+use std::{collections::HashMap, env};
+
+use amqprs::channel::BasicPublishArguments;
+
+use crate::{nfe_parser::Identificacao, rabbitmq::dead_letter::TIPOS_EXCHANGE};
+
+/// Campos aceitos no template de roteamento.
+const CAMPOS: [&str; 4] = ["doc_type", "modelo", "tpAmb", "uf"];
+/// Valor do campo que o documento não informa.
+const DESCONHECIDO: &str = "unknown";
+
+/// Roteamento dos resultados por tipo de documento. Sem template nem tabela, todos os
+/// resultados seguem para `RABBITMQ_EXCHANGE` com `RABBITMQ_ROUTING_KEY`.
+#[derive(Clone, Default)]
+pub struct Roteamento {
+    /// Template da chave de roteamento, ex.: `{doc_type}.{modelo}.{tpAmb}.{uf}`.
+    pub template: Option<String>,
+    /// Exchange por tipo de documento (`doc_type`).
+    pub exchanges: HashMap<String, String>,
+    /// Tipo com que as exchanges por tipo de documento são declaradas.
+    pub exchange_type: String,
+}
+
+pub fn initialize_variables() -> Roteamento {
+    let template: Option<String> = env::var("RABBITMQ_ROUTING_TEMPLATE").ok().filter(|t| !t.trim().is_empty());
+    if let Some(template) = &template {
+        validar_template(template);
+    }
+
+    let mut exchanges: HashMap<String, String> = HashMap::new();
+    if let Ok(valor) = env::var("RABBITMQ_ROUTING_EXCHANGES") {
+        for par in valor.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let Some((tipo, exchange)) = par.split_once('=') else {
+                panic!("Invalid RABBITMQ_ROUTING_EXCHANGES: expected doc_type=exchange, found '{}'", par);
+            };
+            exchanges.insert(tipo.trim().to_string(), exchange.trim().to_string());
+        }
+    }
+
+    let exchange_type: String = env::var("RABBITMQ_ROUTING_EXCHANGE_TYPE")
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+        .map(|t| t.trim().to_lowercase())
+        .unwrap_or("topic".to_string());
+    if !TIPOS_EXCHANGE.contains(&exchange_type.as_str()) {
+        panic!("Invalid RABBITMQ_ROUTING_EXCHANGE_TYPE '{}', expected one of {:?}", exchange_type, TIPOS_EXCHANGE);
+    }
+
+    Roteamento { template, exchanges, exchange_type }
+}
+
+/// Campos desconhecidos ou chaves não fechadas no template interrompem a inicialização.
+fn validar_template(template: &str) {
+    let mut resto: &str = template;
+    while let Some(inicio) = resto.find('{') {
+        let Some(fim) = resto[inicio..].find('}') else {
+            panic!("Invalid RABBITMQ_ROUTING_TEMPLATE '{}': unclosed '{{'", template);
+        };
+        let campo: &str = &resto[inicio + 1..inicio + fim];
+        if !CAMPOS.contains(&campo) {
+            panic!("Invalid RABBITMQ_ROUTING_TEMPLATE '{}': unknown field '{}', expected any of {:?}", template, campo, CAMPOS);
+        }
+        resto = &resto[inicio + fim + 1..];
+    }
+}
+
+impl Roteamento {
+    /// Argumentos de publicação do resultado, a partir dos argumentos padrão.
+    pub fn publish_args(&self, identificacao: &Identificacao, padrao: &BasicPublishArguments) -> BasicPublishArguments {
+        let mut args: BasicPublishArguments = padrao.clone();
+        if let Some(exchange) = self.exchanges.get(identificacao.tipo) {
+            args.exchange = exchange.clone();
+        }
+        if let Some(template) = &self.template {
+            args.routing_key = routing_key(template, identificacao);
+        }
+        args
+    }
+}
+
+fn routing_key(template: &str, identificacao: &Identificacao) -> String {
+    let campo = |valor: &Option<String>| -> String {
+        valor.as_deref().map(segmento).unwrap_or(DESCONHECIDO.to_string())
+    };
+    template
+        .replace("{doc_type}", identificacao.tipo)
+        .replace("{modelo}", &campo(&identificacao.modelo))
+        .replace("{tpAmb}", &campo(&identificacao.tpAmb))
+        .replace("{uf}", &campo(&identificacao.uf))
+}
+
+/// Remove `.`, `*` e `#`, que mudariam o significado da chave em uma topic exchange.
+fn segmento(valor: &str) -> String {
+    valor.chars().filter(|c| !matches!(c, '.' | '*' | '#')).collect()
+}
@@ -9,6 +9,7 @@ mod nfes;
 
 mod nfe_parser;
 mod minio_client;
+mod falha;
 
 
 #[tokio::main]
@@ -18,6 +19,7 @@ async fn main() {
 
     let variables: minio_client::MinioVariables = minio_client::initialize_variables();
     minio_client::init_client(&variables);
+    nfe::config::init_config(nfe::config::initialize_variables());
 
     //let file: Bytes = minio_client::download_object("NFCe33250627708310000111650010006827679001864260.xml", &variables.bucket_name).await.expect("Failed");
 
@@ -29,7 +31,7 @@ async fn main() {
     log::info!("Tempo total parse: {:?}", elapsed);
 
     let json: Vec<u8> = match json {
-        Ok(j) => j,
+        Ok(j) => j.json,
         Err(e) => {
             log::error!("Erro: {}", e);
             panic!();